@@ -0,0 +1,119 @@
+//! Interop with futures 0.3 `Abortable` work, so code built on abort
+//! handles can be stopped by the same exit.
+
+use futures_util::future::{AbortHandle, Abortable, Aborted};
+use futures_util::stream::Stream;
+use pin_project_lite::pin_project;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use {Exit, FireHook, Shared};
+
+impl<T: Send + 'static> Exit<T> {
+    /// Abort `handle` once the signal fires, or right away if it already
+    /// has, until the returned hook is dropped.
+    ///
+    /// Only the first fire is observed: the handle stays aborted after a
+    /// reset.
+    pub fn abort_on_exit(&self, handle: AbortHandle) -> FireHook {
+        Shared::hook(&self.shared, Box::new(move |_| {
+            handle.abort();
+            false
+        }))
+    }
+
+    /// Wrap a future or stream so it's aborted once the signal fires, like
+    /// `Abortable` with a registration tied to this exit.
+    pub fn abortable<F>(&self, work: F) -> ExitAbortable<F> {
+        let (handle, registration) = AbortHandle::new_pair();
+        ExitAbortable { inner: Abortable::new(work, registration), _hook: self.abort_on_exit(handle) }
+    }
+}
+
+pin_project! {
+    /// Future or stream which is aborted once an exit fires. Created by
+    /// `Exit::abortable`.
+    ///
+    /// The exit stops tracking it once it's dropped.
+    pub struct ExitAbortable<F> {
+        #[pin]
+        inner: Abortable<F>,
+        _hook: FireHook,
+    }
+}
+
+impl<F> ExitAbortable<F> {
+    /// Whether the work has been aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.inner.is_aborted()
+    }
+}
+
+impl<F: Future> Future for ExitAbortable<F> {
+    type Output = Result<F::Output, Aborted>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().inner.poll(cx)
+    }
+}
+
+impl<S: Stream> Stream for ExitAbortable<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        self.project().inner.poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::{self, Aborted, FutureExt};
+    use futures_util::stream::{self as stream, StreamExt};
+
+    #[test]
+    fn aborts_on_fire() {
+        let (signal, exit) = ::signal();
+        let mut work = exit.abortable(future::pending::<()>());
+        assert_eq!((&mut work).now_or_never(), None);
+
+        signal.fire().unwrap();
+        assert!(work.is_aborted());
+        assert_eq!(work.now_or_never(), Some(Err(Aborted)));
+    }
+
+    #[test]
+    fn aborts_streams() {
+        let (signal, exit) = ::signal();
+        let mut items = exit.abortable(stream::iter(vec![1, 2]));
+        assert_eq!(items.next().now_or_never(), Some(Some(1)));
+
+        signal.fire().unwrap();
+        assert_eq!(items.next().now_or_never(), Some(None));
+    }
+
+    #[test]
+    fn aborts_when_already_fired() {
+        let (signal, exit) = ::signal();
+        let (handle, registration) = future::AbortHandle::new_pair();
+        drop(signal);
+
+        let _hook = exit.abort_on_exit(handle.clone());
+        assert!(handle.is_aborted());
+        let work = future::Abortable::new(future::ready(5), registration);
+        assert_eq!(work.now_or_never(), Some(Err(Aborted)));
+    }
+
+    #[test]
+    fn finished_work_unregisters() {
+        let (_signal, exit) = ::signal();
+        let work = exit.abortable(future::ready(5));
+        assert_eq!(work.now_or_never(), Some(Ok(5)));
+
+        let (handle, _registration) = future::AbortHandle::new_pair();
+        drop(exit.abort_on_exit(handle.clone()));
+        assert!(exit.shared.waiting.lock().hooks.is_empty());
+        assert!(!handle.is_aborted());
+    }
+}
@@ -0,0 +1,96 @@
+//! Sharing one shutdown source with actix-web servers, whose
+//! `actix_web::dev::{Server, ServerHandle}` come from `actix-server`.
+
+use actix_server::{Server, ServerHandle};
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use {Exit, FireHook, Shared, Signal};
+
+impl<T: Send + 'static> Exit<T> {
+    /// Stop the server behind `handle` once the signal fires, or right away
+    /// if it already has, waiting for open connections if `graceful`.
+    ///
+    /// Nothing is stopped if the returned hook is dropped first.
+    pub fn stop_actix(&self, handle: ServerHandle, graceful: bool) -> FireHook {
+        Shared::hook(&self.shared, Box::new(move |_| {
+            // the stop command is sent right away; the future only waits
+            // for it to complete.
+            drop(handle.stop(graceful));
+            false
+        }))
+    }
+}
+
+impl<T: Default> Signal<T> {
+    /// Run `server`, firing this signal once it stops, for instance on
+    /// ctrl-c or through its handle.
+    pub fn run_actix(self, server: Server) -> ActixServer<T> {
+        ActixServer { server, signal: Some(self) }
+    }
+}
+
+/// Future running an actix server, which fires the signal once the server
+/// stops. Created by `Signal::run_actix`.
+pub struct ActixServer<T = ()> {
+    server: Server,
+    signal: Option<Signal<T>>,
+}
+
+impl<T: Default> Future for ActixServer<T> {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let res = match Pin::new(&mut self.server).poll(cx) {
+            Poll::Ready(res) => res,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        if let Some(signal) = self.signal.take() {
+            let _ = signal.fire();
+        }
+
+        Poll::Ready(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::System;
+    use actix_rt::net::TcpStream;
+    use actix_service::fn_service;
+    use std::future;
+    use super::*;
+
+    #[test]
+    fn shares_shutdown() {
+        let (signal, exit) = ::signal();
+        let (stopped, stopped_exit) = ::signal();
+        let mut start = Some((signal, stopped));
+        let mut running = None;
+
+        // async blocks need a later edition, so drive the setup by hand.
+        let res = System::new().block_on(future::poll_fn(move |cx| {
+            if let Some((signal, stopped)) = start.take() {
+                let server = Server::build()
+                    .workers(1)
+                    .disable_signals()
+                    .bind("test", "127.0.0.1:0", || fn_service(|_: TcpStream| future::ready(Ok::<_, ()>(()))))
+                    .unwrap()
+                    .run();
+
+                exit.stop_actix(server.handle(), true).forget();
+                running = Some(stopped.run_actix(server));
+                signal.fire().unwrap();
+            }
+
+            Pin::new(running.as_mut().unwrap()).poll(cx)
+        }));
+
+        assert!(res.is_ok());
+        assert!(!stopped_exit.is_live());
+    }
+}
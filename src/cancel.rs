@@ -0,0 +1,135 @@
+//! Conversions with tokio-util's `CancellationToken`, so tokio code can share
+//! one shutdown with exits without bridging tasks.
+
+use tokio_util::sync::CancellationToken;
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use compat03::Compat03;
+use {generation, Exit, FireHook, Shared, Signal};
+
+// fires once cancelled.
+impl From<CancellationToken> for Exit {
+    fn from(token: CancellationToken) -> Exit {
+        Exit::from_future(Compat03(Box::pin(token.cancelled_owned())))
+    }
+}
+
+// cancelled once the signal fires.
+impl<T: Send + 'static> From<Exit<T>> for CancellationToken {
+    fn from(exit: Exit<T>) -> CancellationToken {
+        exit.to_cancellation_token()
+    }
+}
+
+// fires the signal once cancelled, observed through its exits.
+impl From<Signal> for CancellationToken {
+    fn from(signal: Signal) -> CancellationToken {
+        let token = CancellationToken::new();
+        signal.shared.add_driver(Box::new(Compat03(Box::pin(token.clone().cancelled_owned()))));
+        signal.defuse();
+
+        token
+    }
+}
+
+// cancelled when the new signal fires.
+impl<T: Send + 'static> From<CancellationToken> for Signal<T> {
+    fn from(token: CancellationToken) -> Signal<T> {
+        let signal = ::signal_only_with();
+        signal.cancel_on_fire(token).forget();
+
+        signal
+    }
+}
+
+fn cancel_on_fire<T: Send + 'static>(shared: &Arc<Shared<T>>, token: CancellationToken) -> FireHook {
+    Shared::hook(shared, Box::new(move |_| {
+        token.cancel();
+        false
+    }))
+}
+
+impl<T: Send + 'static> Exit<T> {
+    /// Get a cancellation token which is cancelled once the signal fires.
+    ///
+    /// Only the first fire is observed: the token stays cancelled after a
+    /// reset. Tokens are shared until then, so calling this repeatedly
+    /// doesn't pile up hooks.
+    pub fn to_cancellation_token(&self) -> CancellationToken {
+        let mut cached = self.shared.cancellation_token.lock();
+        let generation = generation(self.shared.state.load(Ordering::Acquire));
+        if let Some((cached_generation, ref token)) = *cached {
+            if cached_generation == generation {
+                return token.clone()
+            }
+        }
+
+        let token = CancellationToken::new();
+        // the hook goes once it has run, at most one per generation.
+        cancel_on_fire(&self.shared, token.clone()).forget();
+        *cached = Some((generation, token.clone()));
+
+        token
+    }
+}
+
+impl<T: Send + 'static> Signal<T> {
+    /// Cancel `token` once this signal fires, or right away if it already
+    /// has, unless the returned hook is dropped first.
+    pub fn cancel_on_fire(&self, token: CancellationToken) -> FireHook {
+        cancel_on_fire(&self.shared, token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "blocking")]
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn token_from_exit() {
+        let (signal, exit) = ::signal();
+        let token = CancellationToken::from(exit.clone());
+        let _other = exit.to_cancellation_token();
+        assert_eq!(exit.shared.waiting.lock().hooks.len(), 1);
+        assert!(!token.is_cancelled());
+
+        signal.fire().unwrap();
+        assert!(token.is_cancelled());
+        assert!(exit.to_cancellation_token().is_cancelled());
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn exit_from_token() {
+        let token = CancellationToken::new();
+        let exit = Exit::from(token.child_token());
+        assert!(!exit.wait_timeout(Duration::from_millis(10)));
+
+        token.cancel();
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn signal_and_token() {
+        let token = CancellationToken::new();
+        let signal = Signal::<()>::from(token.clone());
+        let exit = signal.make_exit();
+        let other = CancellationToken::new();
+        drop(signal.cancel_on_fire(other.clone()));
+        signal.fire().unwrap();
+        assert!(token.is_cancelled());
+        assert!(!other.is_cancelled());
+        assert!(!exit.is_live());
+
+        let (signal, exit) = ::signal();
+        let token = CancellationToken::from(signal);
+        assert!(!exit.wait_timeout(Duration::from_millis(10)));
+        token.cancel();
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+}
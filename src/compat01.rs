@@ -0,0 +1,35 @@
+//! Polling futures 0.1 futures, such as exits, as std futures, for servers
+//! and runtimes built on `std::future`.
+
+use futures::executor::{Notify, NotifyHandle, Spawn};
+use futures::{Async, Future};
+
+use std::future::Future as StdFuture;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+// wakes the std task polling a futures 0.1 future.
+struct WakerNotify(Waker);
+
+impl Notify for WakerNotify {
+    fn notify(&self, _: usize) {
+        self.0.wake_by_ref()
+    }
+}
+
+// a futures 0.1 future polled as a std future.
+pub(crate) struct Compat01<F>(pub(crate) Spawn<F>);
+
+impl<F: Future + Unpin> StdFuture for Compat01<F> {
+    type Output = Result<F::Item, F::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let notify = NotifyHandle::from(Arc::new(WakerNotify(cx.waker().clone())));
+        match self.0.poll_future_notify(&notify, 0) {
+            Ok(Async::Ready(item)) => Poll::Ready(Ok(item)),
+            Ok(Async::NotReady) => Poll::Pending,
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+}
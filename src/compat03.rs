@@ -0,0 +1,34 @@
+//! Polling futures 0.3 futures, such as those of other shutdown crates, as
+//! futures 0.1 futures.
+
+use futures::{Async, Future, Poll};
+use futures::task::{self, Task};
+
+use std::future::Future as StdFuture;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{self as std_task, Context, Wake, Waker};
+
+// wakes the futures 0.1 task polling a futures 0.3 future.
+struct TaskWaker(Task);
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.notify()
+    }
+}
+
+pub(crate) struct Compat03<F>(pub(crate) F);
+
+impl<F: StdFuture<Output = ()> + Unpin> Future for Compat03<F> {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        let waker = Waker::from(Arc::new(TaskWaker(task::current())));
+        match Pin::new(&mut self.0).poll(&mut Context::from_waker(&waker)) {
+            std_task::Poll::Ready(()) => Ok(Async::Ready(())),
+            std_task::Poll::Pending => Ok(Async::NotReady),
+        }
+    }
+}
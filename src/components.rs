@@ -0,0 +1,181 @@
+use std::borrow::ToOwned;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+use phases::{PhasedShutdown, Stage};
+use {Exit, TrackerToken};
+
+/// Error registering a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterError {
+    /// A component with this name is already registered.
+    Duplicate(String),
+    /// The dependencies would form a cycle through this component.
+    Cycle(String),
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegisterError::Duplicate(ref name) => write!(f, "component {:?} is already registered", name),
+            RegisterError::Cycle(ref name) => write!(f, "dependencies of {:?} form a cycle", name),
+        }
+    }
+}
+
+impl Error for RegisterError {}
+
+struct Component {
+    deps: Vec<String>,
+    stage: Stage,
+}
+
+/// Registry of named components that shuts them down in dependency order.
+///
+/// A component is torn down, and its exit fired, only once every component
+/// depending on it has finished. Dependencies may name components that are
+/// registered later; ones that are never registered are ignored.
+#[derive(Default)]
+pub struct Components {
+    components: BTreeMap<String, Component>,
+}
+
+impl Components {
+    /// Create an empty registry.
+    pub fn new() -> Components {
+        Components::default()
+    }
+
+    /// Register a component depending on `deps`, getting an exit that fires
+    /// when it should shut down, and a token to drop once it has.
+    pub fn register(&mut self, name: &str, deps: &[&str]) -> Result<(Exit, TrackerToken), RegisterError> {
+        if self.components.contains_key(name) {
+            return Err(RegisterError::Duplicate(name.to_owned()))
+        }
+
+        if deps.iter().any(|dep| self.reaches(dep, name)) {
+            return Err(RegisterError::Cycle(name.to_owned()))
+        }
+
+        let component = Component {
+            deps: deps.iter().map(|&dep| dep.to_owned()).collect(),
+            stage: Stage::new(),
+        };
+        let registered = component.stage.register();
+        self.components.insert(name.to_owned(), component);
+
+        Ok(registered)
+    }
+
+    // whether `to` is `from` or one of its transitive dependencies.
+    fn reaches(&self, from: &str, to: &str) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![from];
+
+        while let Some(name) = stack.pop() {
+            if name == to {
+                return true
+            }
+
+            if seen.insert(name) {
+                if let Some(component) = self.components.get(name) {
+                    stack.extend(component.deps.iter().map(|dep| &dep[..]));
+                }
+            }
+        }
+
+        false
+    }
+
+    /// The order that components will be torn down in, dependents first.
+    pub fn teardown_order(&self) -> Vec<&str> {
+        let mut order = Vec::with_capacity(self.components.len());
+        let mut seen = BTreeSet::new();
+
+        for name in self.components.keys() {
+            self.visit(name, &mut seen, &mut order);
+        }
+
+        // dependencies come first in the visit order.
+        order.reverse();
+        order
+    }
+
+    fn visit<'a>(&'a self, name: &'a str, seen: &mut BTreeSet<&'a str>, order: &mut Vec<&'a str>) {
+        let component = match self.components.get(name) {
+            Some(component) => component,
+            None => return,
+        };
+
+        if !seen.insert(name) {
+            return
+        }
+
+        for dep in &component.deps {
+            self.visit(dep, seen, order);
+        }
+        order.push(name);
+    }
+
+    /// Start the shutdown, getting a future which resolves once every
+    /// component has been torn down.
+    pub fn shutdown(mut self) -> PhasedShutdown {
+        let order: Vec<String> = self.teardown_order().into_iter().map(|name| name.to_owned()).collect();
+        let stages: VecDeque<Stage> = order.iter()
+            .filter_map(|name| self.components.remove(name))
+            .map(|component| component.stage)
+            .collect();
+
+        PhasedShutdown::new(stages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn dependents_first() {
+        let mut components = Components::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let handles: Vec<_> = [("http", &["db", "metrics"][..]), ("db", &["metrics"][..]), ("metrics", &[][..])]
+            .iter()
+            .map(|&(name, deps)| {
+                let (exit, token) = components.register(name, deps).unwrap();
+                let log = log.clone();
+                thread::spawn(move || {
+                    exit.wait().unwrap();
+                    log.lock().unwrap().push(name);
+                    drop(token);
+                })
+            })
+            .collect();
+
+        assert_eq!(components.teardown_order(), vec!["http", "db", "metrics"]);
+        components.shutdown().wait().unwrap();
+        assert_eq!(*log.lock().unwrap(), vec!["http", "db", "metrics"]);
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let mut components = Components::new();
+        components.register("a", &["b"]).unwrap();
+        components.register("b", &["c"]).unwrap();
+
+        assert_eq!(components.register("c", &["a"]).err(), Some(RegisterError::Cycle("c".into())));
+        assert_eq!(components.register("d", &["d"]).err(), Some(RegisterError::Cycle("d".into())));
+        assert_eq!(components.register("a", &[]).err(), Some(RegisterError::Duplicate("a".into())));
+        assert!(components.register("c", &[]).is_ok());
+    }
+}
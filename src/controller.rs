@@ -0,0 +1,81 @@
+use futures::IntoFuture;
+
+use std::time::Duration;
+
+use {Exit, Signal, Tracked, Tracker, TrackerToken};
+
+/// Bundles a signal with a tracker of the work it stops, for the common case
+/// of firing on shutdown and then waiting for that work to drain.
+pub struct ShutdownController {
+    signal: Signal,
+    tracker: Tracker,
+}
+
+impl ShutdownController {
+    /// Create a controller with no listeners or work in flight.
+    pub fn new() -> ShutdownController {
+        ShutdownController { signal: ::signal_only(), tracker: Tracker::new() }
+    }
+
+    /// Get an exit which fires when shutdown starts.
+    pub fn exit(&self) -> Exit {
+        self.signal.make_exit()
+    }
+
+    /// The tracker for work in flight.
+    pub fn tracker(&self) -> &Tracker {
+        &self.tracker
+    }
+
+    /// Wrap work so that shutdown waits for it.
+    pub fn track<F: IntoFuture>(&self, f: F) -> Tracked<F::Future> {
+        self.tracker.track(f)
+    }
+
+    /// Get a token that shutdown waits for until it's dropped.
+    pub fn token(&self) -> TrackerToken {
+        self.tracker.token()
+    }
+
+    /// Fire the exit and block until the work in flight drains, returning
+    /// `false` if `timeout` elapsed first.
+    pub fn shutdown(self, timeout: Duration) -> bool {
+        let _ = self.signal.fire();
+        self.tracker.wait_timeout(timeout)
+    }
+}
+
+impl Default for ShutdownController {
+    fn default() -> ShutdownController {
+        ShutdownController::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{future, Future};
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn drains_before_deadline() {
+        let controller = ShutdownController::new();
+        let handles: Vec<_> = (0..3).map(|_| {
+            let work = controller.track(controller.exit());
+            thread::spawn(move || work.wait())
+        }).collect();
+
+        assert!(controller.shutdown(Duration::from_millis(5000)));
+        for handle in handles {
+            assert!(handle.join().unwrap().is_ok());
+        }
+    }
+
+    #[test]
+    fn deadline_exceeded() {
+        let controller = ShutdownController::new();
+        let _work = controller.track(future::empty::<(), ()>());
+
+        assert!(!controller.shutdown(Duration::from_millis(10)));
+    }
+}
@@ -0,0 +1,92 @@
+//! Bridging exits into threaded code that selects over `crossbeam-channel`
+//! receivers, created by `Exit::as_crossbeam_receiver`.
+
+use crossbeam_channel::{self, Receiver};
+
+use std::fmt;
+use std::ops::Deref;
+
+use {Exit, FireHook, Shared};
+
+/// A crossbeam receiver which yields the reason once a signal fires, then
+/// disconnects. Created by `Exit::as_crossbeam_receiver`.
+///
+/// It derefs to the receiver, so it can be used in `crossbeam::select!`
+/// as is, and stops listening for the fire once dropped.
+pub struct ExitChannel<T> {
+    rx: Receiver<T>,
+    _hook: FireHook,
+}
+
+impl<T> Deref for ExitChannel<T> {
+    type Target = Receiver<T>;
+
+    fn deref(&self) -> &Receiver<T> {
+        &self.rx
+    }
+}
+
+impl<T> fmt::Debug for ExitChannel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExitChannel").field("rx", &self.rx).finish()
+    }
+}
+
+impl<T: Clone + Default + Send + 'static> Exit<T> {
+    /// Get a receiver that yields the reason once the signal fires, then
+    /// disconnects, so the exit can be waited on in `crossbeam::select!`.
+    ///
+    /// The receiver only observes the first fire: it stays disconnected
+    /// after a reset. An exit created by `Exit::from_future` only fires
+    /// while it or one of its clones is polled.
+    pub fn as_crossbeam_receiver(&self) -> ExitChannel<T> {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        let hook = Shared::hook(&self.shared, Box::new(move |reason: Option<&T>| {
+            let _ = tx.try_send(reason.cloned().unwrap_or_default());
+            false
+        }));
+
+        ExitChannel { rx, _hook: hook }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::{never, select};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn ready_once_fired() {
+        let (signal, exit) = ::signal_with::<u32>();
+        let rx = exit.as_crossbeam_receiver();
+        assert!(rx.try_recv().is_err());
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            signal.fire_with(3).unwrap();
+        });
+
+        let reason = select! {
+            recv(rx) -> reason => reason,
+            recv(never::<u32>()) -> _ => unreachable!(),
+        };
+        assert_eq!(reason, Ok(3));
+        assert!(rx.recv().is_err());
+    }
+
+    #[test]
+    fn already_fired() {
+        let (signal, exit) = ::signal();
+        drop(signal);
+
+        assert_eq!(exit.as_crossbeam_receiver().recv(), Ok(()));
+    }
+
+    #[test]
+    fn dropped_receivers_unregister() {
+        let (_signal, exit) = ::signal();
+        drop(exit.as_crossbeam_receiver());
+        assert!(exit.shared.waiting.lock().hooks.is_empty());
+    }
+}
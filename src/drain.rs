@@ -0,0 +1,81 @@
+use futures::prelude::*;
+
+use Exit;
+
+/// Future that runs work to completion, letting it know once the exit
+/// signal fires, and only then releases the exit. Created by `Exit::watch`.
+///
+/// Until it completes or is dropped, it holds up `Signal::fire_and_wait`,
+/// so the signal side sees every in-flight request finish.
+pub struct Watching<F, G, T = ()> {
+    inner: F,
+    on_fire: Option<G>,
+    exit: Option<Exit<T>>,
+}
+
+impl<F, G, T> Watching<F, G, T> {
+    pub(crate) fn new(exit: Exit<T>, inner: F, on_fire: G) -> Watching<F, G, T> {
+        Watching { inner, on_fire: Some(on_fire), exit: Some(exit) }
+    }
+}
+
+impl<F: Future, G: FnOnce(&mut F), T: Clone + Default> Future for Watching<F, G, T> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<F::Item, F::Error> {
+        // tell the work before polling it again, so it can start winding
+        // down right away.
+        if let Some(ref mut exit) = self.exit {
+            if self.on_fire.is_some() && exit.check().is_ready() {
+                if let Some(on_fire) = self.on_fire.take() {
+                    on_fire(&mut self.inner);
+                }
+            }
+        }
+
+        let res = self.inner.poll();
+        if !matches!(res, Ok(Async::NotReady)) {
+            // release as soon as the work is done.
+            self.exit = None;
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use futures::sync::oneshot;
+    use super::*;
+
+    #[test]
+    fn drains_watched_work() {
+        let (signal, exit) = ::signal();
+        let (tx, rx) = oneshot::channel::<u32>();
+        let mut tx = Some(tx);
+        let mut work = exit.watch(rx, move |_| {
+            let _ = tx.take().unwrap().send(5);
+        });
+
+        future::lazy(move || {
+            assert_eq!(work.poll(), Ok(Async::NotReady));
+
+            let mut drained = signal.fire_and_wait();
+            assert_eq!(drained.poll(), Ok(Async::NotReady));
+            assert_eq!(work.poll(), Ok(Async::Ready(5)));
+            assert_eq!(drained.poll(), Ok(Async::Ready(())));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn completes_without_fire() {
+        let (signal, exit) = ::signal();
+        let work = exit.watch(future::ok::<_, ()>(1), |_| unreachable!());
+
+        assert_eq!(work.wait(), Ok(1));
+        assert_eq!(signal.fire(), Err(::FireError::NoListeners));
+    }
+}
@@ -0,0 +1,197 @@
+//! Exits that fire once an arbitrary future completes, created by
+//! `Exit::from_future`.
+//!
+//! The future is stored with the exit's shared state and polled by whichever
+//! handle is polled or blocked on, under a notify handle that wakes all of
+//! them, so it needs no executor of its own.
+
+use futures::executor::{self, Notify, NotifyHandle, Spawn};
+use futures::prelude::*;
+use futures::sync::oneshot;
+
+use std::sync::{Arc, Weak};
+use std::sync::atomic::Ordering;
+
+use {Exit, Shared, Signal};
+
+pub(crate) struct Driver {
+    future: Spawn<Box<dyn Future<Item = (), Error = ()> + Send>>,
+    notify: NotifyHandle,
+}
+
+struct WakeAll {
+    shared: Weak<Shared<()>>,
+}
+
+impl Notify for WakeAll {
+    fn notify(&self, _: usize) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.wake_driven();
+        }
+    }
+}
+
+impl<T> Shared<T> {
+    // polls the future behind the exit, if any, firing once it completes.
+    pub(crate) fn drive(&self) {
+        if !self.driven.load(Ordering::Acquire) {
+            return
+        }
+
+        let done = match self.driver.try_lock() {
+            Some(mut driver) => {
+                let done = match *driver {
+                    Some(ref mut driver) => !matches!(driver.future.poll_future_notify(&driver.notify, 0), Ok(Async::NotReady)),
+                    None => false,
+                };
+                if done {
+                    *driver = None;
+                    self.driven.store(false, Ordering::Release);
+                }
+                done
+            }
+            // another handle is polling it, and will wake the rest.
+            None => false,
+        };
+
+        if done {
+            let _ = self.set(None);
+        }
+    }
+
+    fn wake_driven(&self) {
+        let wake_up = {
+            let waiting = self.lock();
+            self.woken.store(true, Ordering::Release);
+            #[cfg(feature = "blocking")]
+            self.blocked.notify_all();
+            waiting.tasks.values().cloned().collect::<Vec<_>>()
+        };
+
+        for task in wake_up {
+            task.notify()
+        }
+    }
+}
+
+impl Shared<()> {
+    // drives `future` from the exits, firing once it completes or once any
+    // future already driving them does.
+    pub(crate) fn add_driver(self: &Arc<Self>, future: Box<dyn Future<Item = (), Error = ()> + Send>) {
+        let mut driver = self.driver.lock();
+        let future: Box<dyn Future<Item = (), Error = ()> + Send> = match driver.take() {
+            Some(old) => Box::new(old.future.into_inner().select(future).then(|_| Ok(()))),
+            None => future,
+        };
+
+        let notify = NotifyHandle::from(Arc::new(WakeAll { shared: Arc::downgrade(self) }));
+        *driver = Some(Driver { future: executor::spawn(future), notify });
+        self.driven.store(true, Ordering::Release);
+    }
+}
+
+impl Exit {
+    /// Get an exit which fires once `f` completes, whether it succeeds or
+    /// fails.
+    ///
+    /// The future is polled by the exit and its clones, so it only makes
+    /// progress while one of them is polled or blocked on.
+    pub fn from_future<F>(f: F) -> Exit
+    where
+        F: IntoFuture,
+        F::Future: Send + 'static,
+    {
+        let signal = ::signal_only();
+        let exit = signal.make_exit();
+        signal.defuse();
+
+        exit.shared.add_driver(Box::new(f.into_future().then(|_| Ok(()))));
+
+        exit
+    }
+}
+
+// fires once the sender completes the channel by sending or dropping.
+impl From<oneshot::Receiver<()>> for Exit {
+    fn from(rx: oneshot::Receiver<()>) -> Exit {
+        Exit::from_future(rx)
+    }
+}
+
+impl Signal {
+    /// Turn this handle into a oneshot sender, which fires the signal when
+    /// it sends or is dropped.
+    ///
+    /// Like `Exit::from_future`, the channel is watched by the exits, so
+    /// the fire is only observed while one of them is polled or blocked on.
+    pub fn into_oneshot_sender(self) -> oneshot::Sender<()> {
+        let (tx, rx) = oneshot::channel();
+        self.shared.add_driver(Box::new(rx.then(|_| Ok(()))));
+        self.defuse();
+
+        tx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use std::thread;
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn fires_when_future_completes() {
+        let (tx, rx) = oneshot::channel::<()>();
+        let exit = Exit::from_future(rx);
+        let clone = exit.clone();
+        assert!(exit.is_live());
+
+        let handle = thread::spawn(move || clone.wait());
+        thread::sleep(Duration::from_millis(20));
+        tx.send(()).unwrap();
+
+        assert!(handle.join().unwrap().is_ok());
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    fn fires_on_error() {
+        let exit = Exit::from_future(future::err::<(), _>("failed"));
+        assert_eq!(::futures::Future::wait(exit), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn from_oneshot_receiver() {
+        let (tx, rx) = oneshot::channel();
+        let exit = Exit::from(rx);
+        assert!(!exit.wait_timeout(Duration::from_millis(10)));
+
+        tx.send(()).unwrap();
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn into_oneshot_sender() {
+        let (signal, exit) = ::signal();
+        let other = signal.clone().into_oneshot_sender();
+        let tx = signal.into_oneshot_sender();
+        assert!(!exit.wait_timeout(Duration::from_millis(10)));
+
+        drop(tx);
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+        drop(other);
+    }
+
+    #[test]
+    fn pending_future() {
+        let mut exit = Exit::from_future(future::empty::<(), ()>());
+
+        future::lazy(move || {
+            assert_eq!(exit.poll(), Ok(Async::NotReady));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use futures::prelude::*;
+
+use {Signal, WeakSignal};
+
+/// Future that runs essential work and fires a signal once it finishes,
+/// created by `essential`.
+pub struct Essential<F, T = ()> {
+    inner: F,
+    signal: WeakSignal<T>,
+}
+
+impl<F: Future, T: Default> Future for Essential<F, T> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<F::Item, F::Error> {
+        let res = self.inner.poll();
+        if let Ok(Async::NotReady) = res {
+            return res
+        }
+
+        let _ = self.signal.fire();
+        res
+    }
+}
+
+/// Run `f`, firing `signal` once it completes, whether it succeeds or fails.
+/// Use it for work the rest of the process can't go on without.
+///
+/// The work holds the signal weakly, like `Signal::downgrade`, so it doesn't
+/// keep the signal from firing on drop.
+pub fn essential<F: IntoFuture, T>(signal: &Signal<T>, f: F) -> Essential<F::Future, T> {
+    Essential { inner: f.into_future(), signal: signal.downgrade() }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use super::*;
+
+    #[test]
+    fn fires_on_completion() {
+        let (signal, exit) = ::signal();
+        let work = essential(&signal, future::ok::<_, ()>(3));
+        assert!(exit.is_live());
+
+        assert_eq!(work.wait(), Ok(3));
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    fn fires_on_error() {
+        let (signal, exit) = ::signal();
+        let work = essential(&signal, future::err::<(), _>("network task died"));
+
+        assert_eq!(work.wait(), Err("network task died"));
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    fn holds_signal_weakly() {
+        let (signal, exit) = ::signal();
+        let _work = essential(&signal, future::empty::<(), ()>());
+
+        drop(signal);
+        assert!(!exit.is_live());
+    }
+}
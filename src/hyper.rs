@@ -0,0 +1,251 @@
+//! Graceful shutdown for hyper servers: a signal future for the accept
+//! loop, connections watched through hyper-util's `GracefulShutdown` so
+//! they shut down gracefully once the exit fires, and tracking of the ones
+//! still open so shutdown can wait for them to drain, up to a deadline.
+//!
+//! The signal and drain futures also suit other servers built on
+//! `std::future`, counting their connections with
+//! `GracefulShutdown::connection`.
+
+use futures::executor;
+use futures::future::{self, Either};
+use futures::Future;
+use hyper_util::server::graceful::{self, GracefulConnection};
+use parking_lot::Mutex;
+use pin_project_lite::pin_project;
+
+use std::future::Future as StdFuture;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use compat01::Compat01;
+use timer::Delay;
+use {Exit, FireHook, Shared, Tracker, TrackerToken, TrackerWait};
+
+/// Tracks a server's open connections and hands out the signal that starts
+/// its graceful shutdown.
+#[derive(Clone)]
+pub struct GracefulShutdown<T = ()> {
+    pub(crate) exit: Exit<T>,
+    pub(crate) tracker: Tracker,
+    // hyper-util's, dropped once the exit fires, which starts the graceful
+    // shutdown of every connection it watches.
+    watching: Arc<Mutex<Option<graceful::GracefulShutdown>>>,
+    _hook: Arc<FireHook>,
+}
+
+impl<T: Send + 'static> GracefulShutdown<T> {
+    /// Shut down gracefully once `exit` fires.
+    pub fn new(exit: &Exit<T>) -> GracefulShutdown<T> {
+        let watching = Arc::new(Mutex::new(Some(graceful::GracefulShutdown::new())));
+        let weak = Arc::downgrade(&watching);
+        let hook = Shared::hook(&exit.shared, Box::new(move |_| {
+            if let Some(watching) = weak.upgrade() {
+                drop(watching.lock().take());
+            }
+            false
+        }));
+
+        GracefulShutdown { exit: exit.clone(), tracker: Tracker::new(), watching, _hook: Arc::new(hook) }
+    }
+}
+
+impl<T> GracefulShutdown<T> {
+    /// Watch a hyper connection, such as the one made by
+    /// `hyper::server::conn::http1::Builder::serve_connection`, so it shuts
+    /// down gracefully once the exit fires and counts as open until it
+    /// closes. Spawn the returned future in place of the connection.
+    ///
+    /// The connection types hyper-util can watch depend on its `http1` and
+    /// `http2` features.
+    pub fn watch<C: GracefulConnection>(&self, conn: C) -> impl StdFuture<Output = C::Output> {
+        let conn = match *self.watching.lock() {
+            Some(ref watching) => watching.watch(conn),
+            // already shutting down: watched by one that's dropped right
+            // away, so the connection starts shutting down when polled.
+            None => graceful::GracefulShutdown::new().watch(conn),
+        };
+
+        Watched { conn, _token: self.tracker.token() }
+    }
+
+    /// Get a future which resolves once the exit fires, to stop accepting
+    /// connections, or for a server's `with_graceful_shutdown`.
+    pub fn signal(&self) -> ShutdownSignal<T> {
+        ShutdownSignal { exit: Compat01(executor::spawn(self.exit.clone())) }
+    }
+
+    /// Get a token which counts as an open connection until it's dropped,
+    /// for connections that aren't passed to `watch`.
+    ///
+    /// Move it into the per-connection service, so it's dropped when the
+    /// connection closes.
+    pub fn connection(&self) -> TrackerToken {
+        self.tracker.token()
+    }
+
+    /// The number of open connections.
+    pub fn connections(&self) -> usize {
+        self.tracker.len()
+    }
+
+    /// Get a future which resolves once every connection has closed, to
+    /// `true`, or `timeout` from now has elapsed, to `false`.
+    pub fn drain(&self, timeout: Duration) -> Drain {
+        // a timeout too large to represent is the same as none at all.
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(at) => Either::A(Delay::new(at)),
+            None => Either::B(future::empty()),
+        };
+
+        Drain { inner: Compat01(executor::spawn(self.tracker.wait().select2(deadline))) }
+    }
+}
+
+pin_project! {
+    // a watched connection, counted as open until it's dropped.
+    struct Watched<F> {
+        #[pin]
+        conn: F,
+        _token: TrackerToken,
+    }
+}
+
+impl<F: StdFuture> StdFuture for Watched<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        self.project().conn.poll(cx)
+    }
+}
+
+/// Future that resolves once the exit signal fires. Created by
+/// `GracefulShutdown::signal`.
+pub struct ShutdownSignal<T = ()> {
+    exit: Compat01<Exit<T>>,
+}
+
+impl<T: Clone + Default> StdFuture for ShutdownSignal<T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(&mut self.exit).poll(cx).map(|_| ())
+    }
+}
+
+type Deadline = Either<Delay, future::Empty<(), ()>>;
+
+/// Future that resolves to whether every connection closed before the
+/// deadline. Created by `GracefulShutdown::drain`.
+pub struct Drain {
+    inner: Compat01<future::Select2<TrackerWait, Deadline>>,
+}
+
+impl StdFuture for Drain {
+    type Output = bool;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        Pin::new(&mut self.inner).poll(cx).map(|res| matches!(res, Ok(Either::A(_)) | Err(Either::A(_))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hyper;
+
+    use self::hyper::body::Incoming;
+    use self::hyper::server::conn::http1;
+    use self::hyper::service::service_fn;
+    use self::hyper::{Request, Response};
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread::{self, Thread};
+    use std::task::{Wake, Waker};
+    use super::*;
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark()
+        }
+    }
+
+    fn block_on<F: StdFuture>(f: F) -> F::Output {
+        let mut f = Box::pin(f);
+        let waker = Waker::from(Arc::new(Unpark(thread::current())));
+        loop {
+            if let Poll::Ready(out) = f.as_mut().poll(&mut Context::from_waker(&waker)) {
+                return out
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn drains_connections() {
+        let (signal, exit) = ::signal();
+        let graceful = GracefulShutdown::new(&exit);
+        let connection = graceful.connection();
+        assert_eq!(graceful.connections(), 1);
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            signal.fire().unwrap();
+            thread::sleep(Duration::from_millis(20));
+            drop(connection);
+        });
+
+        block_on(graceful.signal());
+        assert!(block_on(graceful.drain(Duration::from_millis(5000))));
+        assert_eq!(graceful.connections(), 0);
+    }
+
+    #[test]
+    fn drain_deadline() {
+        let (_signal, exit) = ::signal();
+        let graceful = GracefulShutdown::new(&exit);
+        let _connection = graceful.connection();
+
+        assert!(!block_on(graceful.drain(Duration::from_millis(20))));
+    }
+
+    #[test]
+    fn shuts_down_hyper_connections() {
+        let (signal, exit) = ::signal();
+        let graceful = GracefulShutdown::new(&exit);
+        let runtime = ::tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let listener = runtime.block_on(::tokio::net::TcpListener::bind("127.0.0.1:0")).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"GET / HTTP/1.1\r\nHost: test\r\n\r\n").unwrap();
+
+            // the connection is kept alive until the server shuts it down.
+            let mut response = Vec::new();
+            let mut buf = [0; 1024];
+            while !response.ends_with(b"hello") {
+                let read = stream.read(&mut buf).unwrap();
+                response.extend_from_slice(&buf[..read]);
+            }
+            signal.fire().unwrap();
+            assert_eq!(stream.read(&mut buf).unwrap(), 0);
+        });
+
+        let (stream, _) = runtime.block_on(listener.accept()).unwrap();
+        let service = service_fn(|_: Request<Incoming>| ::std::future::ready(Ok::<_, Infallible>(Response::new(String::from("hello")))));
+        let conn = http1::Builder::new().serve_connection(TokioIo::new(stream), service);
+        let served = runtime.spawn(graceful.watch(conn));
+        assert_eq!(graceful.connections(), 1);
+
+        runtime.block_on(graceful.signal());
+        assert!(runtime.block_on(graceful.drain(Duration::from_millis(5000))));
+        assert!(runtime.block_on(served).unwrap().is_ok());
+        client.join().unwrap();
+    }
+}
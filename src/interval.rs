@@ -0,0 +1,96 @@
+use futures::prelude::*;
+
+use std::time::{Duration, Instant};
+
+use timer::Delay;
+use Exit;
+
+/// Stream that ticks with the scheduled time of each tick, every period,
+/// until the exit signal fires. Created by `Exit::interval`.
+///
+/// Ticks that were missed because the stream wasn't polled in time are
+/// skipped rather than yielded in a burst.
+pub struct Interval<T = ()> {
+    exit: Exit<T>,
+    period: Duration,
+    // `None` once the next tick is too far away to represent.
+    next: Option<(Instant, Delay)>,
+    done: bool,
+}
+
+impl<T> Interval<T> {
+    pub(crate) fn new(exit: Exit<T>, period: Duration) -> Interval<T> {
+        assert!(period > Duration::from_secs(0), "interval period must be non-zero");
+
+        let next = Instant::now().checked_add(period).map(|at| (at, Delay::new(at)));
+        Interval { exit, period, next, done: false }
+    }
+}
+
+impl<T: Clone + Default> Stream for Interval<T> {
+    type Item = Instant;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Instant>, ()> {
+        if self.done {
+            return Ok(Async::Ready(None))
+        }
+
+        if self.exit.check().is_ready() {
+            self.done = true;
+            return Ok(Async::Ready(None))
+        }
+
+        let at = match self.next {
+            Some((at, ref mut delay)) => {
+                try_ready!(delay.poll());
+                at
+            }
+            None => return Ok(Async::NotReady),
+        };
+
+        let now = Instant::now();
+        let mut next = at.checked_add(self.period);
+        if next.is_some_and(|next| next <= now) {
+            next = now.checked_add(self.period);
+        }
+        self.next = next.map(|next| (next, Delay::new(next)));
+
+        Ok(Async::Ready(Some(at)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use super::*;
+
+    #[test]
+    fn ticks_until_exit() {
+        let (signal, exit) = ::signal();
+        let start = Instant::now();
+        let mut ticks = exit.interval(Duration::from_millis(10)).wait();
+
+        let first = ticks.next().unwrap().unwrap();
+        let second = ticks.next().unwrap().unwrap();
+        assert!(first >= start + Duration::from_millis(10));
+        assert!(second >= first + Duration::from_millis(10));
+
+        signal.fire().unwrap();
+        assert!(ticks.next().is_none());
+    }
+
+    #[test]
+    fn exit_ends_pending_tick() {
+        let (signal, exit) = ::signal();
+        let mut ticks = exit.interval(Duration::from_secs(3600));
+
+        future::lazy(move || {
+            assert_eq!(ticks.poll(), Ok(Async::NotReady));
+            signal.fire().unwrap();
+            assert_eq!(ticks.poll(), Ok(Async::Ready(None)));
+            assert_eq!(ticks.poll(), Ok(Async::Ready(None)));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+}
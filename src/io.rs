@@ -0,0 +1,176 @@
+use std::io::{self, Read, Write};
+
+use {Cancelled, Exit};
+
+// not `Interrupted`, which `read_exact`, `write_all` and `io::copy` retry
+// forever.
+fn cancelled() -> io::Error {
+    io::Error::other(Cancelled)
+}
+
+/// Reader that fails with an `ErrorKind::Other` error wrapping `Cancelled`
+/// once the exit signal fires. Created by `Exit::until_read`.
+///
+/// The exit is checked before every read, so this works on any thread,
+/// inside a task or not. A read that is already blocked isn't interrupted.
+pub struct ExitRead<R, T = ()> {
+    inner: R,
+    exit: Exit<T>,
+}
+
+impl<R, T> ExitRead<R, T> {
+    pub(crate) fn new(inner: R, exit: Exit<T>) -> ExitRead<R, T> {
+        ExitRead { inner, exit }
+    }
+
+    /// Get a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Take back the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read, T> Read for ExitRead<R, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.exit.is_live() {
+            return Err(cancelled())
+        }
+
+        self.inner.read(buf)
+    }
+}
+
+/// Writer that fails with an `ErrorKind::Other` error wrapping `Cancelled`
+/// once the exit signal fires. Created by `Exit::until_write`.
+///
+/// The exit is checked before every write and flush, like `ExitRead`.
+pub struct ExitWrite<W, T = ()> {
+    inner: W,
+    exit: Exit<T>,
+}
+
+impl<W, T> ExitWrite<W, T> {
+    pub(crate) fn new(inner: W, exit: Exit<T>) -> ExitWrite<W, T> {
+        ExitWrite { inner, exit }
+    }
+
+    /// Get a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Take back the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write, T> Write for ExitWrite<W, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.exit.is_live() {
+            return Err(cancelled())
+        }
+
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.exit.is_live() {
+            return Err(cancelled())
+        }
+
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use futures::prelude::*;
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn reads_until_exit_fires() {
+        let (signal, exit) = ::signal();
+        let mut reader = exit.until_read(&b"hello"[..]);
+
+        future::lazy(move || {
+            let mut buf = [0; 2];
+            assert_eq!(reader.read(&mut buf).unwrap(), 2);
+            assert_eq!(&buf, b"he");
+
+            signal.fire().unwrap();
+            let err = reader.read(&mut buf).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::Other);
+            assert!(err.get_ref().unwrap().is::<Cancelled>());
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn writes_until_exit_fires() {
+        let (signal, exit) = ::signal();
+        let mut writer = exit.until_write(Vec::new());
+
+        future::lazy(move || {
+            writer.write_all(b"hi").unwrap();
+            writer.flush().unwrap();
+
+            signal.fire().unwrap();
+            assert_eq!(writer.write(b"!").unwrap_err().kind(), io::ErrorKind::Other);
+            assert_eq!(writer.flush().unwrap_err().kind(), io::ErrorKind::Other);
+            assert_eq!(writer.into_inner(), b"hi");
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn std_loops_stop_once_fired() {
+        let (signal, exit) = ::signal();
+        let mut reader = exit.clone().until_read(&b"hello"[..]);
+        let mut writer = exit.until_write(Vec::new());
+        signal.fire().unwrap();
+
+        future::lazy(move || {
+            let err = io::copy(&mut reader, &mut io::sink()).unwrap_err();
+            assert!(err.get_ref().unwrap().is::<Cancelled>());
+            assert!(reader.read_exact(&mut [0; 2]).is_err());
+            assert!(reader.read_to_end(&mut Vec::new()).is_err());
+            assert!(io::copy(&mut &b"hi"[..], &mut writer).is_err());
+            assert!(writer.write_all(b"hi").is_err());
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn works_outside_tasks() {
+        let (signal, exit) = ::signal();
+        let mut reader = exit.clone().until_read(&b"hello"[..]);
+        let mut writer = exit.until_write(Vec::new());
+
+        thread::spawn(move || {
+            assert_eq!(reader.read(&mut [0; 2]).unwrap(), 2);
+            writer.write_all(b"hi").unwrap();
+            writer.flush().unwrap();
+
+            signal.fire().unwrap();
+            assert!(reader.read(&mut [0; 2]).is_err());
+            assert!(writer.write(b"!").is_err());
+        }).join().unwrap();
+    }
+}
@@ -1,274 +1,3192 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(all(test, feature = "actix"))]
+extern crate actix_rt;
+#[cfg(feature = "actix")]
+extern crate actix_server;
+#[cfg(all(test, feature = "actix"))]
+extern crate actix_service;
+#[cfg(feature = "axum")]
+extern crate axum;
+#[cfg(feature = "crossbeam")]
+extern crate crossbeam_channel;
+#[macro_use]
 extern crate futures;
+#[cfg(any(feature = "abort", feature = "tokio"))]
+extern crate futures_util;
+#[cfg(feature = "hyper")]
+extern crate hyper_util;
+#[cfg(feature = "os")]
+extern crate libc;
+#[cfg(feature = "std")]
 extern crate parking_lot;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "stop-token")]
+extern crate stop_token;
+#[cfg(any(feature = "abort", feature = "hyper", feature = "tower"))]
+extern crate pin_project_lite;
+#[cfg(feature = "tower")]
+extern crate tower_layer;
+#[cfg(any(feature = "tokio", all(test, any(feature = "axum", feature = "hyper"))))]
+extern crate tokio;
+#[cfg(feature = "tokio")]
+extern crate tokio_util;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(any(feature = "tower", feature = "axum"))]
+extern crate tower_service;
+#[cfg(feature = "triggered")]
+extern crate triggered;
+#[cfg(not(any(feature = "std", test)))]
+#[allow(unused_imports)]
+#[macro_use]
+extern crate alloc;
 
-use parking_lot::Mutex;
+#[cfg(feature = "blocking")]
+use parking_lot::Condvar;
 use futures::prelude::*;
-use futures::task::{self, Task, AtomicTask};
-use futures::executor::{self, Notify};
+use futures::future::Either;
+use futures::task::AtomicTask;
+
+use lock::{Mutex, MutexGuard};
+#[cfg(feature = "tracing")]
+use std::borrow::Cow;
+use std::boxed::Box;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::panic::{RefUnwindSafe, UnwindSafe};
+use std::sync::{Arc, Weak};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+use std::ptr;
+use std::sync::atomic::{self, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+// stands in for the parts of std used by the crate when building without it.
+// some of them are only used by optional modules.
+#[cfg(not(any(feature = "std", test)))]
+#[allow(unused_imports)]
+mod std {
+    pub use core::*;
+    pub use alloc::{borrow, boxed, rc, string, vec};
+
+    pub mod collections {
+        pub use alloc::collections::*;
+    }
+
+    pub mod sync {
+        pub use alloc::sync::{Arc, Weak};
+        pub use core::sync::atomic;
+    }
+}
 
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "abort")]
+mod abort;
+#[cfg(feature = "actix")]
+mod actix;
+#[cfg(feature = "tokio")]
+mod cancel;
+#[cfg(any(feature = "hyper", feature = "tokio"))]
+mod compat01;
+#[cfg(any(feature = "stop-token", feature = "tokio", feature = "triggered"))]
+mod compat03;
+#[cfg(feature = "components")]
+mod components;
+#[cfg(feature = "blocking")]
+mod controller;
+#[cfg(feature = "crossbeam")]
+mod crossbeam;
+#[cfg(feature = "combinators")]
+mod drain;
+#[cfg(feature = "std")]
+mod driven;
+#[cfg(feature = "essential")]
+mod essential;
+#[cfg(feature = "timer")]
+mod interval;
+#[cfg(all(feature = "combinators", feature = "std"))]
+mod io;
+#[cfg(feature = "local")]
+mod local;
+mod lock;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "multi")]
+mod multi;
+#[cfg(feature = "std")]
+mod panic_hook;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+#[cfg(feature = "os")]
+pub mod os;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "phases")]
+mod phases;
+#[cfg(feature = "quorum")]
+mod quorum;
+#[cfg(feature = "reason")]
+mod reason;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "axum")]
+mod serve;
+#[cfg(feature = "timer")]
+mod retry;
+#[cfg(feature = "tokio")]
+mod spawn;
+#[cfg(feature = "staged")]
+mod staged;
+#[cfg(feature = "stop-token")]
+mod stop;
+#[cfg(feature = "combinators")]
+mod stream;
+#[cfg(feature = "blocking")]
+mod sync_exit;
+#[cfg(all(feature = "systemd", unix))]
+pub mod systemd;
+#[cfg(feature = "timer")]
+mod timer;
+#[cfg(feature = "tonic")]
+pub mod tonic;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(feature = "tracker")]
+mod tracker;
+#[cfg(feature = "triggered")]
+mod trigger;
+#[cfg(feature = "tokio")]
+mod watch;
+#[cfg(feature = "timer")]
+mod watchdog;
+#[cfg(feature = "blocking")]
+mod workers;
+
+#[cfg(feature = "abort")]
+pub use abort::ExitAbortable;
+#[cfg(feature = "actix")]
+pub use actix::ActixServer;
+#[cfg(feature = "components")]
+pub use components::{Components, RegisterError};
+#[cfg(feature = "crossbeam")]
+pub use crossbeam::ExitChannel;
+#[cfg(feature = "blocking")]
+pub use controller::ShutdownController;
+#[cfg(feature = "combinators")]
+pub use drain::Watching;
+#[cfg(feature = "essential")]
+pub use essential::{essential, Essential};
+#[cfg(feature = "timer")]
+pub use interval::Interval;
+#[cfg(all(feature = "combinators", feature = "std"))]
+pub use io::{ExitRead, ExitWrite};
+#[cfg(feature = "std")]
+pub use metrics::Metrics;
+#[cfg(feature = "local")]
+pub use local::{local_signal, local_signal_with, LocalExit, LocalSignal, LocalWeakSignal};
+#[cfg(feature = "multi")]
+pub use multi::{multi_signal, ExitStream, MultiSignal};
+#[cfg(feature = "std")]
+pub use panic_hook::install_panic_hook;
+#[cfg(feature = "phases")]
+pub use phases::{PhasedShutdown, Phases};
+#[cfg(feature = "quorum")]
+pub use quorum::{barrier, quorum, QuorumSignal};
+#[cfg(feature = "reason")]
+pub use reason::ExitReason;
+#[cfg(feature = "std")]
+pub use registry::{registry, SignalRegistry};
+#[cfg(feature = "axum")]
+pub use serve::{serve_until, MissingExit, ServeUntil};
+#[cfg(feature = "timer")]
+pub use retry::{Backoff, Retry};
+#[cfg(feature = "staged")]
+pub use staged::{two_stage, TwoStage};
+#[cfg(feature = "tokio")]
+pub use spawn::ExitJoinHandle;
+#[cfg(feature = "combinators")]
+pub use stream::{ExitStreamExt, RecvUntilExit, TakeUntilExit};
+#[cfg(feature = "blocking")]
+pub use sync_exit::{ExitScope, SyncExit};
+#[cfg(feature = "tracker")]
+pub use tracker::{Tracked, Tracker, TrackerToken, TrackerWait};
+#[cfg(feature = "timer")]
+pub use watchdog::Watchdog;
+#[cfg(feature = "blocking")]
+pub use workers::Workers;
 
 /// Future that resolves when inner work finishes or on exit signal firing.
 #[derive(Clone)]
-pub struct UntilExit<F> {
+pub struct UntilExit<F, T = ()> {
     inner: F,
-    exit: Exit,
+    exit: Exit<T>,
 }
 
-impl<F: Future> Future for UntilExit<F> {
+impl<F: Future, T: Clone + Default> Future for UntilExit<F, T> {
     type Item = Option<F::Item>;
     type Error = F::Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         match self.inner.poll() {
             Ok(Async::Ready(x)) => Ok(Async::Ready(Some(x))),
-            Ok(Async::NotReady) => Ok(self.exit.check().map(|()| None)),
+            Ok(Async::NotReady) => Ok(self.exit.check().map(|_| None)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Future that resolves when inner work finishes or, on exit signal
+/// firing, hands back the unfinished work.
+pub struct UntilKeep<F, T = ()> {
+    inner: Option<F>,
+    exit: Exit<T>,
+}
+
+impl<F: Future, T: Clone + Default> Future for UntilKeep<F, T> {
+    type Item = Either<F::Item, F>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let res = self.inner.as_mut().expect("cannot poll UntilKeep twice").poll();
+        match res {
+            Ok(Async::Ready(x)) => {
+                self.inner = None;
+                Ok(Async::Ready(Either::A(x)))
+            }
+            Ok(Async::NotReady) => match self.exit.check() {
+                Async::Ready(_) => Ok(Async::Ready(Either::B(self.inner.take().unwrap()))),
+                Async::NotReady => Ok(Async::NotReady),
+            },
             Err(e) => Err(e),
         }
     }
 }
 
-struct Notifier {
-    signalled: AtomicBool,
-    outer_task: AtomicTask,
+/// Error returned when work was cancelled by the exit signal firing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("cancelled by exit signal")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Future that resolves when inner work finishes or, on exit signal
+/// firing, to `Err(Cancelled)`.
+#[derive(Clone)]
+pub struct UntilResult<F, T = ()> {
+    inner: UntilExit<F, T>,
+}
+
+impl<F: Future, T: Clone + Default> Future for UntilResult<F, T> {
+    type Item = Result<F::Item, Cancelled>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let res = try_ready!(self.inner.poll());
+        Ok(Async::Ready(res.ok_or(Cancelled)))
+    }
+}
+
+/// Future that resolves when inner work finishes or a grace period after
+/// the exit signal fired.
+#[cfg(feature = "timer")]
+pub struct UntilGraceful<F, T = ()> {
+    inner: F,
+    exit: Exit<T>,
+    grace: Duration,
+    // set once the exit fires, to `None` if the grace period overflows
+    // `Instant`, in which case the work runs to completion.
+    deadline: Option<Option<timer::Delay>>,
+}
+
+#[cfg(feature = "timer")]
+impl<F: Future, T: Clone + Default> Future for UntilGraceful<F, T> {
+    type Item = Option<F::Item>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Async::Ready(x) = self.inner.poll()? {
+            return Ok(Async::Ready(Some(x)))
+        }
+
+        if self.deadline.is_none() {
+            if self.exit.check().is_not_ready() {
+                return Ok(Async::NotReady)
+            }
+
+            self.deadline = Some(Instant::now().checked_add(self.grace).map(timer::Delay::new));
+        }
+
+        match self.deadline.as_mut().unwrap().as_mut().map(Future::poll) {
+            Some(Ok(Async::NotReady)) | None => Ok(Async::NotReady),
+            Some(_) => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+/// Future that resolves when inner work finishes or, on exit signal
+/// firing, once the cleanup has run.
+pub struct UntilWithCleanup<F, G, C: IntoFuture, T = ()> {
+    work: UntilKeep<F, T>,
+    cleanup: Option<G>,
+    cleaning_up: Option<C::Future>,
+}
+
+impl<F, G, C, T> Future for UntilWithCleanup<F, G, C, T>
+    where F: Future, G: FnOnce() -> C, C: IntoFuture<Item = (), Error = F::Error>, T: Clone + Default
+{
+    type Item = Option<F::Item>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if self.cleaning_up.is_none() {
+            match try_ready!(self.work.poll()) {
+                Either::A(x) => return Ok(Async::Ready(Some(x))),
+                // the unfinished work is dropped before the cleanup runs.
+                Either::B(_) => {
+                    let cleanup = self.cleanup.take().expect("cannot poll UntilWithCleanup twice");
+                    self.cleaning_up = Some(cleanup().into_future());
+                }
+            }
+        }
+
+        try_ready!(self.cleaning_up.as_mut().unwrap().poll());
+        Ok(Async::Ready(None))
+    }
+}
+
+/// Future that resolves to `Some` with the exit reason when the exit signal
+/// fires, or to `None` once a deadline passes.
+#[cfg(feature = "timer")]
+pub struct OrAfter<T = ()> {
+    exit: Exit<T>,
+    // `None` when the deadline is too far off to represent, so never passes.
+    deadline: Option<timer::Delay>,
+}
+
+#[cfg(feature = "timer")]
+impl<T: Clone + Default> Future for OrAfter<T> {
+    type Item = Option<T>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<T>, ()> {
+        if let Async::Ready(reason) = self.exit.check() {
+            return Ok(Async::Ready(Some(reason)))
+        }
+
+        match self.deadline {
+            Some(ref mut deadline) => Ok(deadline.poll()?.map(|()| None)),
+            None => Ok(Async::NotReady),
+        }
+    }
+}
+
+/// Future that resolves to `true` once a duration has elapsed, or early to
+/// `false` when the exit signal fires. Created by `Exit::delay`.
+#[cfg(feature = "timer")]
+pub struct Sleep<T = ()> {
+    inner: OrAfter<T>,
 }
 
-impl Notify for Notifier {
-    // id isn't necessary because this is a notifier for a single
-    // exit future.
-    fn notify(&self, _: usize) {
-        self.signalled.store(true, Ordering::Release);
-        self.outer_task.notify()
+#[cfg(feature = "timer")]
+impl<T: Clone + Default> Future for Sleep<T> {
+    type Item = bool;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<bool, ()> {
+        Ok(self.inner.poll()?.map(|reason| reason.is_none()))
     }
 }
 
+// a listener's wakeup slot. it's registered with the shared state once and
+// updated with the current task on every poll.
 struct ExitInner {
     shared_id: usize,
-    notifier: Arc<Notifier>,
+    task: Arc<AtomicTask>,
 }
 
 impl ExitInner {
-    fn check(&self, shared: &Shared) -> Async<()> {
-        // set up the outer task correctly so when the inner
-        // task is notified, we get woken up.
-        self.notifier.outer_task.register();
-
-        // if any poll was signalled by the inner task, use it.
-        if !self.notifier.signalled.fetch_and(false, Ordering::SeqCst) {
-            return Async::NotReady
-        }
-
-        // do heavy check with inner task. ID doesn't matter.
-        executor::with_notify(&self.notifier, 0, || {
-            if shared.is_live_and_notify(self.shared_id) {
-                Async::NotReady
-            } else {
-                Async::Ready(())
-            }
-        })
+    // update the slot before checking, so a concurrent fire can't slip in
+    // between and go unnoticed.
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn register<T>(&self, shared: &Shared<T>) {
+        self.task.register();
+        #[cfg(feature = "std")]
+        shared.drive();
     }
 }
 
 /// Future that resolves when the exit signal has fired.
-pub struct Exit {
+///
+/// It resolves to the reason the signal was fired with, or `T::default()`
+/// if it was fired by being dropped.
+///
+/// An `Exit` is fused: it may be polled again after resolving and keeps
+/// resolving until the signal is reset, so it can be used in `select`-style
+/// loops without `fuse`. Exits cloned or made after the signal fired
+/// resolve on their first poll, without waiting for a notification.
+///
+/// Each exit resolves at least once for every fire, even when the signal
+/// is reset before the exit gets to observe it. See `Signal::reset`.
+///
+/// `Future::into_stream` adapts it into a stream yielding the reason once
+/// when the signal fires, then ending.
+pub struct Exit<T = ()> {
     inner: Option<ExitInner>,
-    shared: Arc<Shared>,
+    shared: Arc<Shared<T>>,
+    // the number of fires this exit has observed, counting the current one
+    // once it resolved.
+    seen: usize,
+    // when the exit was first polled without resolving, for tracing.
+    #[cfg(feature = "tracing")]
+    pending_since: Option<Instant>,
 }
 
-impl Exit {
-    /// Check if the signal is live outside of the context of a task and 
+impl<T> Exit<T> {
+    fn new(shared: Arc<Shared<T>>) -> Exit<T> {
+        shared.listeners.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "std")]
+        if let Some(metrics) = shared.metrics.get() {
+            metrics.listener_registered();
+        }
+
+        // starts out in the current generation, so a fire in progress counts.
+        let seen = generation(shared.state.load(Ordering::Acquire));
+        Exit {
+            inner: None,
+            shared,
+            seen,
+            #[cfg(feature = "tracing")]
+            pending_since: None,
+        }
+    }
+
+    /// Check if the signal is live outside of the context of a task and
     /// without scheduling a wakeup.
+    ///
+    /// This is consistent with a concurrent `fire`: once it has returned
+    /// `false`, it keeps returning `false` until the signal is reset, and
+    /// the exit future is guaranteed to resolve, even if the reset comes
+    /// before it is polled.
     pub fn is_live(&self) -> bool {
-        self.shared.waiting.lock().0
+        self.shared.is_live()
+    }
+
+    // whether there's a fire this exit hasn't observed, or it observed the
+    // current one and the signal is still fired. marks it observed.
+    fn observe(&mut self) -> bool {
+        let state = self.shared.state.load(Ordering::Acquire);
+        let fires = fires(state);
+        if fires > self.seen {
+            self.seen = fires;
+            #[cfg(feature = "tracing")]
+            self.shared.report_resolved(self.pending_since.take().map(|since| since.elapsed()));
+            return true
+        }
+
+        is_fired(state)
+    }
+
+    // whether `observe` would return `true`, without marking anything.
+    #[cfg(feature = "blocking")]
+    fn has_fired(&self, state: usize) -> bool {
+        fires(state) > self.seen || is_fired(state)
+    }
+
+    /// Get a flag which is set exactly when the signal fires, for
+    /// synchronous loops to check without any futures machinery.
+    ///
+    /// The flag is a read-only view of the signal's own state, so it agrees
+    /// with `is_live` and is cleared again by a reset.
+    pub fn as_flag(&self) -> ExitFlag {
+        ExitFlag { state: self.shared.state.clone() }
+    }
+
+    /// Get a receiver that receives once the signal fires, then
+    /// disconnects, for threads waiting in `recv` or `recv_timeout` loops.
+    ///
+    /// The receiver only observes the first fire: it stays disconnected
+    /// after a reset.
+    #[cfg(feature = "std")]
+    pub fn as_mpsc_receiver(&self) -> ExitReceiver where T: Send + 'static {
+        let (tx, rx) = ::std::sync::mpsc::sync_channel(1);
+        let hook = Shared::hook(&self.shared, Box::new(move |_| {
+            let _ = tx.try_send(());
+            false
+        }));
+
+        ExitReceiver { rx, _hook: hook }
+    }
+
+    /// The number of live exits for this signal, including this one.
+    pub fn listener_count(&self) -> usize {
+        self.shared.listener_count()
+    }
+
+    /// The number of exits for this signal that have been polled and are
+    /// registered to be woken when it fires.
+    pub fn waiting_count(&self) -> usize {
+        self.shared.waiting_count()
+    }
+
+    /// Acknowledge that the signal has been handled, by dropping this exit.
+    /// See `Signal::fire_and_wait`.
+    pub fn ack(self) {}
+
+    /// Run `f` to completion, calling `on_fire` with it once the signal
+    /// fires so it can start winding down, and only then acknowledge the
+    /// signal. See `Watching`.
+    #[cfg(feature = "combinators")]
+    pub fn watch<F, G>(self, f: F, on_fire: G) -> Watching<F::Future, G, T>
+        where F: IntoFuture, G: FnOnce(&mut F::Future)
+    {
+        Watching::new(self, f.into_future(), on_fire)
+    }
+
+    /// Resolve when any of the given exits fires, with its reason. Never
+    /// resolves if there are none.
+    pub fn any<I: IntoIterator<Item = Exit<T>>>(exits: I) -> Any<T> {
+        Any { exits: exits.into_iter().collect() }
+    }
+
+    /// Resolve once all of the given exits have fired, with their reasons.
+    /// Resolves immediately if there are none.
+    pub fn all<I: IntoIterator<Item = Exit<T>>>(exits: I) -> All<T> {
+        All { exits: exits.into_iter().collect() }
+    }
+
+    /// Block the current thread until the signal fires or `timeout` has
+    /// elapsed, returning whether it fired.
+    #[cfg(feature = "blocking")]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        // a timeout too large to represent is the same as none at all.
+        self.shared.wait_until(|state| self.has_fired(state), Instant::now().checked_add(timeout))
+    }
+
+    /// Block the current thread until the signal fires or `deadline` is
+    /// reached, returning whether it fired.
+    #[cfg(feature = "blocking")]
+    pub fn wait_deadline(&self, deadline: Instant) -> bool {
+        self.shared.wait_until(|state| self.has_fired(state), Some(deadline))
+    }
+
+    /// Get a blocking view of this exit, for worker threads that don't run
+    /// futures. See `SyncExit`.
+    #[cfg(feature = "blocking")]
+    pub fn into_sync(self) -> SyncExit<T> {
+        SyncExit::from(self)
+    }
+
+    /// Sleep the current thread for `duration`, waking early if the signal
+    /// fires. Returns whether the full duration elapsed.
+    ///
+    /// `delay` is the equivalent for use in a task.
+    #[cfg(feature = "blocking")]
+    pub fn sleep(&self, duration: Duration) -> bool {
+        !self.wait_timeout(duration)
     }
 
     /// Perform given work until complete.
-    pub fn until<F: IntoFuture>(self, f: F) -> UntilExit<F::Future> {
+    ///
+    /// The work is polled before the exit, so work that completes on the
+    /// same poll the exit fires still resolves to its result. The `until_*`
+    /// variants share this bias, unlike the stream combinators, which check
+    /// the exit first.
+    pub fn until<F: IntoFuture>(self, f: F) -> UntilExit<F::Future, T> {
         UntilExit {
             inner: f.into_future(),
             exit: self,
         }
-    } 
+    }
 
-    fn check(&mut self) -> Async<()> {
-        let shared = &self.shared;
+    /// Perform given work until complete. Once the exit fires, the work is
+    /// given up to `grace` to finish before being dropped. A grace period
+    /// too large to represent as an `Instant` lets the work run to completion.
+    #[cfg(feature = "timer")]
+    pub fn until_graceful<F: IntoFuture>(self, f: F, grace: Duration) -> UntilGraceful<F::Future, T> {
+        UntilGraceful {
+            inner: f.into_future(),
+            exit: self,
+            grace,
+            deadline: None,
+        }
+    }
 
-        // lazily register and initialize.
-        let inner = self.inner.get_or_insert_with(|| {
-            let shared_id = shared.register();
-            let notifier = Arc::new(Notifier {
-                // ensure an initial poll happens.
-                signalled: AtomicBool::new(true),
-                outer_task: AtomicTask::new(),
-            });
+    /// Perform given work until complete. If the exit fires first, the work
+    /// is dropped and the future returned by `cleanup` is run to completion.
+    pub fn until_with_cleanup<F, G, C>(self, f: F, cleanup: G) -> UntilWithCleanup<F::Future, G, C, T>
+        where F: IntoFuture, G: FnOnce() -> C, C: IntoFuture<Item = (), Error = F::Error>
+    {
+        UntilWithCleanup {
+            work: self.until_keep(f),
+            cleanup: Some(cleanup),
+            cleaning_up: None,
+        }
+    }
 
-            ExitInner { shared_id, notifier }
-        });
+    /// Resolve when either the exit fires or `timeout` has elapsed from now.
+    ///
+    /// A timeout too large to represent as an `Instant` never elapses.
+    #[cfg(feature = "timer")]
+    pub fn or_after(self, timeout: Duration) -> OrAfter<T> {
+        OrAfter {
+            exit: self,
+            deadline: Instant::now().checked_add(timeout).map(timer::Delay::new),
+        }
+    }
 
-        inner.check(shared)
+    /// Sleep for `duration` without blocking the thread, waking early if
+    /// the signal fires. Resolves to whether the full duration elapsed,
+    /// like `sleep`.
+    ///
+    /// The timer entry is removed if the future is dropped first. Durations
+    /// too large to represent as an `Instant` only end once the signal fires.
+    #[cfg(feature = "timer")]
+    pub fn delay(&self, duration: Duration) -> Sleep<T> {
+        Sleep { inner: self.clone().or_after(duration) }
     }
-}
 
-impl Future for Exit {
-    type Item = ();
-    type Error = ();
+    /// Retry an operation with backoff until it succeeds, resolving to
+    /// `Err(Cancelled)` if the exit fires first. See `Retry`.
+    #[cfg(feature = "timer")]
+    pub fn retry<G: FnMut() -> F, F: IntoFuture>(self, policy: Backoff, operation: G) -> Retry<G, F, T> {
+        Retry::new(self, policy, operation)
+    }
 
-    fn poll(&mut self) -> Poll<(), ()> {
-        Ok(self.check())
+    /// Get a stream ticking every `period`, starting one period from now,
+    /// which ends once the exit fires.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    #[cfg(feature = "timer")]
+    pub fn interval(self, period: Duration) -> Interval<T> {
+        Interval::new(self, period)
     }
-}
 
-impl Clone for Exit {
-    fn clone(&self) -> Exit {
-        Exit { inner: None, shared: self.shared.clone() }
+    /// Perform given work until complete, failing with `Cancelled` if the
+    /// exit fires first.
+    pub fn until_result<F: IntoFuture>(self, f: F) -> UntilResult<F::Future, T> {
+        UntilResult { inner: self.until(f) }
     }
-}
 
-struct Shared {
-    count: AtomicUsize,
-    waiting: Mutex<(bool, HashMap<usize, Task>)>,
-}
+    /// Yield items from the given stream until the exit fires, then end.
+    #[cfg(feature = "combinators")]
+    pub fn until_stream<S: Stream>(self, s: S) -> TakeUntilExit<S, T> {
+        TakeUntilExit::new(s, self)
+    }
 
-impl Shared {
-    fn set(&self) {
-        let wake_up = {
-            let mut waiting = self.waiting.lock();
-            waiting.0 = false;
-            ::std::mem::replace(&mut waiting.1, HashMap::new())
-        };
+    /// Wrap a reader so that reads fail with an `ErrorKind::Other` error
+    /// once the exit fires. See `ExitRead`.
+    #[cfg(all(feature = "combinators", feature = "std"))]
+    pub fn until_read<R: ::std::io::Read>(self, r: R) -> ExitRead<R, T> {
+        ExitRead::new(r, self)
+    }
 
-        for (_, task) in wake_up {
-            task.notify()
+    /// Wrap a writer so that writes and flushes fail with an
+    /// `ErrorKind::Other` error once the exit fires. See `ExitWrite`.
+    #[cfg(all(feature = "combinators", feature = "std"))]
+    pub fn until_write<W: ::std::io::Write>(self, w: W) -> ExitWrite<W, T> {
+        ExitWrite::new(w, self)
+    }
+
+    /// Perform given work until complete. If the exit fires first, the
+    /// unfinished work is returned instead of being dropped.
+    pub fn until_keep<F: IntoFuture>(self, f: F) -> UntilKeep<F::Future, T> {
+        UntilKeep {
+            inner: Some(f.into_future()),
+            exit: self,
         }
     }
+}
 
-    fn register(&self) -> usize {
-        self.count.fetch_add(1, Ordering::Relaxed)
+#[cfg(feature = "std")]
+impl Exit {
+    /// Get an exit which never fires.
+    ///
+    /// Every such exit shares the same static state, so creating one doesn't
+    /// allocate.
+    pub fn never() -> Exit {
+        static NEVER: OnceLock<Arc<Shared<()>>> = OnceLock::new();
+        Exit::new(NEVER.get_or_init(|| Arc::new(Shared::new())).clone())
     }
 
-    // should be called only in the context of a task.
-    // returns whether the exit counter is live.
-    fn is_live_and_notify(&self, id: usize) -> bool {
-        let mut waiting = self.waiting.lock();
+    /// Get an exit which has already fired.
+    ///
+    /// Like `never`, every such exit shares the same static state.
+    pub fn fired() -> Exit {
+        static FIRED: OnceLock<Arc<Shared<()>>> = OnceLock::new();
+        Exit::new(FIRED.get_or_init(|| {
+            let shared = Arc::new(Shared::new());
+            let _ = shared.set(None);
+            shared
+        }).clone())
+    }
+}
+
+impl<T: Clone + Default> Exit<T> {
+    /// Block the current thread until the signal fires, returning the reason.
+    ///
+    /// This shadows `Future::wait`, parking the thread directly rather than
+    /// spinning up an executor task. The error type matches `Future::wait`;
+    /// this never fails. Without the `blocking` feature, `exit.wait()` calls
+    /// `Future::wait` instead.
+    #[cfg(feature = "blocking")]
+    #[allow(clippy::result_unit_err)]
+    pub fn wait(self) -> Result<T, ()> {
+        self.shared.wait_until(|state| self.has_fired(state), None);
+        Ok(self.shared.reason())
+    }
+
+    fn check(&mut self) -> Async<T> {
+        // once fired, resolve without registering or allocating.
+        if self.observe() {
+            return Async::Ready(self.shared.reason())
+        }
+
+        // lazily register and initialize.
+        let shared = &self.shared;
+        let inner = self.inner.get_or_insert_with(|| {
+            let task = Arc::new(AtomicTask::new());
+            let shared_id = shared.register(task.clone());
+
+            ExitInner { shared_id, task }
+        });
+        inner.register(shared);
 
-        if waiting.0 {
-            let _ = waiting.1.insert(id, task::current());
+        if self.observe() {
+            return Async::Ready(self.shared.reason())
         }
 
-        waiting.0
+        #[cfg(feature = "tracing")]
+        {
+            let _ = self.pending_since.get_or_insert_with(Instant::now);
+        }
+        Async::NotReady
     }
 }
 
-/// Exit signal that fires either manually or on drop.
-pub struct Signal {
-    shared: Arc<Shared>,
+impl<T: Clone + Default> Future for Exit<T> {
+    type Item = T;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<T, ()> {
+        Ok(self.check())
+    }
 }
 
-impl Signal {
-    fn fire_inner(&mut self) {
-        self.shared.set();
+// cloning only bumps reference counts: registration is deferred until the
+// clone is first polled.
+impl<T> Clone for Exit<T> {
+    fn clone(&self) -> Exit<T> {
+        Exit::new(self.shared.clone())
     }
+}
+
+// the shared state stays consistent if a panic unwinds through it: the
+// lock doesn't poison, and a panicking hook still lets the exits wake. the
+// reason is only ever read through a shared reference.
+impl<T: RefUnwindSafe> UnwindSafe for Exit<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Exit<T> {}
 
-    /// Fire the signal manually.
-    pub fn fire(mut self) {
-        self.fire_inner()
+impl<T> Drop for Exit<T> {
+    fn drop(&mut self) {
+        if let Some(ref inner) = self.inner {
+            self.shared.deregister(inner.shared_id);
+        }
+
+        self.shared.release_listener();
     }
+}
 
-    /// Get an exit future.
-    pub fn make_exit(&self) -> Exit {
-        Exit { inner: None, shared: self.shared.clone() }
+impl<T> fmt::Debug for Exit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Exit")
+            .field("fired", &!self.shared.is_live())
+            .field("listeners", &self.shared.listeners.load(Ordering::Relaxed))
+            .finish()
     }
 }
 
-impl Drop for Signal {
-    fn drop(&mut self) {
-        self.fire_inner()
+/// Read-only view of whether a signal has fired. Created by
+/// `Exit::as_flag`.
+///
+/// Unlike an `Exit`, it doesn't count as a listener.
+#[derive(Clone)]
+pub struct ExitFlag {
+    state: Arc<AtomicUsize>,
+}
+
+impl ExitFlag {
+    /// Whether the signal has fired, loaded with `Ordering::Acquire` so
+    /// everything done before the fire is visible.
+    pub fn is_set(&self) -> bool {
+        self.load(Ordering::Acquire)
+    }
+
+    /// Whether the signal has fired, loaded with the given ordering.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is `Release` or `AcqRel`, like `AtomicUsize::load`.
+    pub fn load(&self, order: Ordering) -> bool {
+        is_fired(self.state.load(order))
     }
 }
 
-/// Create a signal and exit pair. `Exit` is a future that resolves when the
-/// `Signal` object is either dropped or has `fire` called on it.
-pub fn signal() -> (Signal, Exit) {
-    let signal = signal_only();
-    let exit = signal.make_exit();
+impl fmt::Debug for ExitFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExitFlag").field("set", &self.is_set()).finish()
+    }
+}
 
-    (signal, exit)
+/// An `mpsc::Receiver` which receives once a signal fires, then
+/// disconnects. Created by `Exit::as_mpsc_receiver`.
+///
+/// It derefs to the receiver, and stops listening for the fire once
+/// dropped.
+#[cfg(feature = "std")]
+pub struct ExitReceiver {
+    rx: ::std::sync::mpsc::Receiver<()>,
+    _hook: FireHook,
 }
 
-/// Create only a signal.
-pub fn signal_only() -> Signal {
-    let shared = Arc::new(Shared {
-        count: AtomicUsize::new(1),
-        waiting: Mutex::new((true, HashMap::new())),
-    });
+#[cfg(feature = "std")]
+impl Deref for ExitReceiver {
+    type Target = ::std::sync::mpsc::Receiver<()>;
 
-    Signal { shared }
+    fn deref(&self) -> &::std::sync::mpsc::Receiver<()> {
+        &self.rx
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use futures::future;
-    use super::*;
+#[cfg(feature = "std")]
+impl fmt::Debug for ExitReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExitReceiver").finish_non_exhaustive()
+    }
+}
 
-    #[test]
-    fn it_works() {
-        let (signal, exit_a) = signal();
-        let exit_b = exit_a.clone();
-        let exit_c = exit_b.clone();
+/// Future that resolves once every `Exit` of a signal has been dropped.
+/// Created by `Signal::wait_idle` and `Signal::fire_and_wait`.
+pub struct Idle<T = ()> {
+    shared: Arc<Shared<T>>,
+    slot: Option<(usize, Arc<AtomicTask>)>,
+}
 
-        assert!(exit_a.is_live() && exit_b.is_live());
+impl<T> Future for Idle<T> {
+    type Item = ();
+    type Error = ();
 
-        let barrier = Arc::new(::std::sync::Barrier::new(2));
-        let thread_barrier = barrier.clone();
-        let handle = ::std::thread::spawn(move || {
-            let barrier = ::futures::future::lazy(move || { thread_barrier.wait(); Ok(()) });
+    fn poll(&mut self) -> Poll<(), ()> {
+        if self.shared.is_idle() {
+            return Ok(Async::Ready(()))
+        }
 
-            assert!(exit_a.join3(exit_b, barrier).wait().is_ok());
+        let shared = &self.shared;
+        let &mut (_, ref task) = self.slot.get_or_insert_with(|| {
+            let task = Arc::new(AtomicTask::new());
+            (shared.register_idle(task.clone()), task)
         });
 
-        barrier.wait();
-        signal.fire();
+        // as with exits, update the slot before checking.
+        task.register();
 
-        let _ = handle.join();
-        assert!(!exit_c.is_live());
-        assert!(exit_c.wait().is_ok());
+        if shared.is_idle() {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
     }
+}
 
-    #[test]
-    fn exit_signal_are_send_and_sync() {
-        fn is_send_and_sync<T: Send + Sync>() {}
-
-        is_send_and_sync::<Exit>();
-        is_send_and_sync::<Signal>();
+impl<T> Drop for Idle<T> {
+    fn drop(&mut self) {
+        if let Some((id, _)) = self.slot {
+            let _ = self.shared.lock().idle.remove(&id);
+        }
     }
+}
 
-    #[test]
-    fn work_until() {
-        let (signal, exit) = signal();
-        let work_a = exit.clone().until(future::ok::<_, ()>(5));
-        assert_eq!(work_a.wait().unwrap(), Some(5));
+/// Future that fired a signal and resolves with the number of exits it
+/// notified, once every `Exit` has observed the fire by being dropped.
+/// Created by `Signal::fire_async`.
+pub struct FireAsync<T = ()> {
+    // taken once the error, if any, has been returned.
+    fired: Option<Result<usize, FireError>>,
+    idle: Idle<T>,
+}
 
-        signal.fire();
-        let work_b = exit.until(::futures::future::empty::<(), ()>());
-        assert_eq!(work_b.wait().unwrap(), None);
+impl<T> Future for FireAsync<T> {
+    type Item = usize;
+    type Error = FireError;
+
+    fn poll(&mut self) -> Poll<usize, FireError> {
+        let notified = match self.fired {
+            Some(Ok(notified)) => notified,
+            Some(Err(e)) => return Err(e),
+            None => panic!("cannot poll FireAsync twice"),
+        };
+
+        match self.idle.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            _ => {
+                self.fired = None;
+                Ok(Async::Ready(notified))
+            }
+        }
     }
+}
 
-    #[test]
-    fn works_from_other_thread() {
-        let (signal, exit) = signal();
+/// Future that resolves with the reason of the first of several exits to
+/// fire. Created by `Exit::any`.
+pub struct Any<T = ()> {
+    exits: Vec<Exit<T>>,
+}
 
-        ::std::thread::spawn(move || {
-            ::std::thread::sleep(::std::time::Duration::from_millis(2500));
-            signal.fire();
-        });
+impl<T: Clone + Default> Future for Any<T> {
+    type Item = T;
+    type Error = ();
 
-        exit.wait().unwrap();
+    fn poll(&mut self) -> Poll<T, ()> {
+        for exit in &mut self.exits {
+            if let Async::Ready(reason) = exit.check() {
+                return Ok(Async::Ready(reason))
+            }
+        }
+
+        Ok(Async::NotReady)
     }
+}
 
-    #[test]
-    fn clone_works() {
+/// Future that resolves with the reasons of several exits, in order, once
+/// all of them have fired. Created by `Exit::all`.
+pub struct All<T = ()> {
+    exits: Vec<Exit<T>>,
+}
+
+impl<T: Clone + Default> Future for All<T> {
+    type Item = Vec<T>;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Vec<T>, ()> {
+        // exits are fused, so ones which already fired can be polled again.
+        let mut reasons = Vec::with_capacity(self.exits.len());
+        for exit in &mut self.exits {
+            match exit.check() {
+                Async::Ready(reason) => reasons.push(reason),
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+
+        Ok(Async::Ready(reasons))
+    }
+}
+
+/// Extension trait to run any future until an exit fires.
+pub trait ExitExt: Future + Sized {
+    /// Run this future until complete or `exit` fires, like `Exit::until`.
+    fn until_exit<T>(self, exit: &Exit<T>) -> UntilExit<Self, T> {
+        exit.clone().until(self)
+    }
+
+    /// Wait for this future, such as a `oneshot::Receiver`, resolving to
+    /// `Err(Cancelled)` if `exit` fires first, like `Exit::until_result`.
+    fn recv_until_exit<T>(self, exit: &Exit<T>) -> UntilResult<Self, T> {
+        exit.clone().until_result(self)
+    }
+}
+
+impl<F: Future> ExitExt for F {}
+
+/// Operations shared by `Signal` and `LocalSignal`, so code can be generic
+/// over threaded and single-threaded signals.
+pub trait SignalHandle: Clone {
+    /// The reason exits resolve to.
+    type Reason;
+    /// The exit future made by the signal.
+    type Exit: ExitHandle<Item = Self::Reason>;
+
+    /// Fire the signal, resolving exits to `reason`. See `Signal::fire_with`.
+    fn fire_with(&self, reason: Self::Reason) -> Result<usize, FireError>;
+
+    /// Fire the signal with the default reason. See `Signal::fire`.
+    fn fire(&self) -> Result<usize, FireError> where Self::Reason: Default {
+        self.fire_with(Self::Reason::default())
+    }
+
+    /// Get an exit future.
+    fn make_exit(&self) -> Self::Exit;
+
+    /// Whether the signal has already been fired.
+    fn is_fired(&self) -> bool;
+
+    /// The number of live exits for this signal.
+    fn listener_count(&self) -> usize;
+
+    /// Re-arm a fired signal, returning whether it had been fired. See
+    /// `Signal::reset`.
+    fn reset(&self) -> bool;
+
+    /// Create a child signal, which fires along with this one. See
+    /// `Signal::child`.
+    fn child(&self) -> Self;
+
+    /// Consume the signal without firing it. See `Signal::defuse`.
+    fn defuse(self);
+
+    /// Set what dropping this handle does.
+    fn with_drop_behavior(self, on_drop: DropBehavior) -> Self;
+}
+
+/// Operations shared by `Exit` and `LocalExit`, which resolve with the
+/// reason once their signal fires.
+pub trait ExitHandle: Future<Error = ()> + Clone {
+    /// Check if the signal is live without scheduling a wakeup.
+    fn is_live(&self) -> bool;
+
+    /// The number of live exits for the signal, including this one.
+    fn listener_count(&self) -> usize;
+}
+
+/// Error returned when firing a `Signal`.
+///
+/// In both cases the signal is fired once `fire` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FireError {
+    /// The signal has fired, but there was no `Exit` alive to observe it.
+    NoListeners,
+    /// The signal had already been fired.
+    AlreadyFired,
+}
+
+impl fmt::Display for FireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FireError::NoListeners => f.write_str("signal fired with no exit listening"),
+            FireError::AlreadyFired => f.write_str("signal already fired"),
+        }
+    }
+}
+
+impl Error for FireError {}
+
+// run on every fire with the reason, while holding the lock of the signal
+// being fired. returns whether to keep it registered.
+type Hook<T> = Box<dyn FnMut(Option<&T>) -> bool + Send>;
+
+struct Waiting<T> {
+    tasks: BTreeMap<usize, Arc<AtomicTask>>,
+    // woken once there are no listeners left.
+    idle: BTreeMap<usize, Arc<AtomicTask>>,
+    // `None` when fired by drop.
+    reason: Option<T>,
+    hooks: BTreeMap<usize, Hook<T>>,
+    // when the signal last fired, until the drain is reported.
+    #[cfg(feature = "std")]
+    fired_at: Option<Instant>,
+}
+
+// notifies the tasks when dropped, including while unwinding.
+struct WakeUp(Vec<Arc<AtomicTask>>);
+
+impl Drop for WakeUp {
+    fn drop(&mut self) {
+        for task in self.0.drain(..) {
+            task.notify()
+        }
+    }
+}
+
+// the `waiting` lock of a signal. once released, completes the fires
+// claimed by `try_fire` while it was held.
+struct Locked<'a, T: 'a> {
+    shared: &'a Shared<T>,
+    waiting: Option<MutexGuard<'a, Waiting<T>>>,
+    // woken once the lock is released.
+    wake_up: WakeUp,
+}
+
+impl<'a, T> Locked<'a, T> {
+    fn complete(&mut self) {
+        let shared = self.shared;
+        let mut wake_up = shared.complete(self);
+        self.wake_up.0.append(&mut wake_up.0);
+    }
+
+    #[cfg(feature = "blocking")]
+    fn guard(&mut self) -> &mut MutexGuard<'a, Waiting<T>> {
+        self.waiting.as_mut().expect("locked")
+    }
+}
+
+impl<'a, T> Deref for Locked<'a, T> {
+    type Target = Waiting<T>;
+
+    fn deref(&self) -> &Waiting<T> {
+        self.waiting.as_ref().expect("locked")
+    }
+}
+
+impl<'a, T> DerefMut for Locked<'a, T> {
+    fn deref_mut(&mut self) -> &mut Waiting<T> {
+        self.waiting.as_mut().expect("locked")
+    }
+}
+
+impl<'a, T> Drop for Locked<'a, T> {
+    fn drop(&mut self) {
+        drop(self.waiting.take());
+        drop(mem::replace(&mut self.wake_up, WakeUp(Vec::new())));
+        self.shared.help();
+    }
+}
+
+// set in a signal's state while it is fired.
+const FIRED: usize = 1;
+// set by `try_fire` once it has stashed its reason, until whoever holds the
+// lock fires the signal with it. counts as fired.
+const CLAIMED: usize = 2;
+
+// whether a signal's state is fired, or about to be.
+fn is_fired(state: usize) -> bool {
+    state & (FIRED | CLAIMED) != 0
+}
+
+// the number of resets a signal's state has been through.
+fn generation(state: usize) -> usize {
+    state >> 2
+}
+
+// the number of fires a signal's state has been through: one for each
+// generation before the current, and one more if it's fired.
+fn fires(state: usize) -> usize {
+    generation(state) + is_fired(state) as usize
+}
+
+struct Shared<T> {
+    count: AtomicUsize,
+    // number of live `Exit` handles.
+    listeners: AtomicUsize,
+    // number of armed `Signal` handles.
+    signals: AtomicUsize,
+    // the `FIRED` bit and the generation, only changed while holding the
+    // `waiting` lock, except for `try_fire` setting the `CLAIMED` bit, but
+    // read without it. shared with the flags handed out by `Exit::as_flag`.
+    state: Arc<AtomicUsize>,
+    // the reason stashed by `try_fire` while the `CLAIMED` bit is set.
+    pending: AtomicPtr<Option<T>>,
+    // only locked through `Shared::lock`, which completes claimed fires.
+    waiting: Mutex<Waiting<T>>,
+    // set while a thread runs the hooks, holding the `waiting` lock. hooks
+    // removed meanwhile are queued in `unhooked` for it to drop.
+    running_hooks: AtomicBool,
+    unhooked: Mutex<Vec<usize>>,
+    // unregisters a child signal from its parent once the child is gone.
+    parent: Mutex<Option<FireHook>>,
+    // notified on fire and when idle, for blocking waits.
+    #[cfg(feature = "blocking")]
+    blocked: Condvar,
+    // the future behind an `Exit::from_future`, and whether there is one.
+    #[cfg(feature = "std")]
+    driver: Mutex<Option<driven::Driver>>,
+    #[cfg(feature = "std")]
+    driven: AtomicBool,
+    // set when the driven future is notified, so blocking waits don't miss
+    // a wakeup while polling it.
+    #[cfg(feature = "std")]
+    woken: AtomicBool,
+    #[cfg(feature = "std")]
+    metrics: OnceLock<Box<dyn Metrics>>,
+    // given to `Signal::with_name`, for tracing.
+    #[cfg(feature = "tracing")]
+    name: OnceLock<Cow<'static, str>>,
+    // the token or listener handed out for the current generation, so
+    // repeated calls don't each register a hook.
+    #[cfg(feature = "stop-token")]
+    stop_token: Mutex<Option<(usize, stop_token::StopToken)>>,
+    #[cfg(feature = "triggered")]
+    listener: Mutex<Option<(usize, triggered::Listener)>>,
+    #[cfg(feature = "tokio")]
+    cancellation_token: Mutex<Option<(usize, tokio_util::sync::CancellationToken)>>,
+    #[cfg(feature = "tokio")]
+    watch_receiver: Mutex<Option<(usize, tokio::sync::watch::Receiver<bool>)>>,
+}
+
+impl<T> Shared<T> {
+    fn new() -> Shared<T> {
+        Shared {
+            count: AtomicUsize::new(1),
+            listeners: AtomicUsize::new(0),
+            signals: AtomicUsize::new(1),
+            state: Arc::new(AtomicUsize::new(0)),
+            pending: AtomicPtr::new(ptr::null_mut()),
+            waiting: Mutex::new(Waiting {
+                tasks: BTreeMap::new(),
+                idle: BTreeMap::new(),
+                reason: None,
+                hooks: BTreeMap::new(),
+                #[cfg(feature = "std")]
+                fired_at: None,
+            }),
+            running_hooks: AtomicBool::new(false),
+            unhooked: Mutex::new(Vec::new()),
+            parent: Mutex::new(None),
+            #[cfg(feature = "blocking")]
+            blocked: Condvar::new(),
+            #[cfg(feature = "std")]
+            driver: Mutex::new(None),
+            #[cfg(feature = "std")]
+            driven: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            woken: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            metrics: OnceLock::new(),
+            #[cfg(feature = "tracing")]
+            name: OnceLock::new(),
+            #[cfg(feature = "stop-token")]
+            stop_token: Mutex::new(None),
+            #[cfg(feature = "triggered")]
+            listener: Mutex::new(None),
+            #[cfg(feature = "tokio")]
+            cancellation_token: Mutex::new(None),
+            #[cfg(feature = "tokio")]
+            watch_receiver: Mutex::new(None),
+        }
+    }
+
+    // takes the `waiting` lock, first completing any fire claimed by
+    // `try_set`. completes the ones claimed meanwhile once released.
+    fn lock(&self) -> Locked<'_, T> {
+        let mut waiting = self.waiting.lock();
+        let wake_up = self.complete(&mut waiting);
+        Locked { shared: self, waiting: Some(waiting), wake_up }
+    }
+
+    // returns the number of live exits notified.
+    fn set(&self, reason: Option<T>) -> Result<usize, FireError> {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+
+        let wake_up = {
+            let mut waiting = self.lock();
+            let state = self.state.load(Ordering::Acquire);
+            // `try_set` may claim the fire until the bit is set.
+            if is_fired(state) || self.state.compare_exchange(state, state | FIRED, Ordering::SeqCst, Ordering::Acquire).is_err() {
+                return Err(FireError::AlreadyFired)
+            }
+
+            self.fired(&mut waiting, reason)
+        };
+        drop(wake_up);
+
+        #[cfg(feature = "std")]
+        self.report_fired(start.elapsed());
+
+        self.fire_result()
+    }
+
+    // like `set`, but without waiting on the lock: claims the fire, then
+    // leaves it to whoever holds the lock if it's taken.
+    fn try_set(&self, reason: Option<T>) -> Result<usize, FireError> {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+
+        // only one reason can be stashed at a time. if there's another, its
+        // fire is in progress or it's about to find the signal fired.
+        let pending = Box::into_raw(Box::new(reason));
+        if self.pending.compare_exchange(ptr::null_mut(), pending, Ordering::AcqRel, Ordering::Acquire).is_err() {
+            drop(unsafe { Box::from_raw(pending) });
+            return Err(FireError::AlreadyFired)
+        }
+
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            if is_fired(state) {
+                // nothing takes the reason unless it's claimed.
+                drop(unsafe { Box::from_raw(self.pending.swap(ptr::null_mut(), Ordering::AcqRel)) });
+                return Err(FireError::AlreadyFired)
+            }
+
+            match self.state.compare_exchange_weak(state, state | CLAIMED, Ordering::SeqCst, Ordering::Acquire) {
+                Ok(_) => break,
+                Err(actual) => state = actual,
+            }
+        }
+        self.help();
+
+        #[cfg(feature = "std")]
+        self.report_fired(start.elapsed());
+
+        self.fire_result()
+    }
+
+    #[cfg(feature = "std")]
+    fn report_fired(&self, latency: Duration) {
+        if let Some(metrics) = self.metrics.get() {
+            metrics.fired(latency);
+        }
+
+        #[cfg(feature = "tracing")]
+        ::tracing::info!(
+            signal = self.name(),
+            listeners = self.listeners.load(Ordering::Acquire),
+            latency = ?latency,
+            "exit signal fired",
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    fn name(&self) -> &str {
+        self.name.get().map_or("unnamed", |name| name)
+    }
+
+    // reports an exit observing a fire, `pending` after it started waiting
+    // for it, if it did.
+    #[cfg(feature = "tracing")]
+    fn report_resolved(&self, pending: Option<Duration>) {
+        match pending {
+            Some(pending) => ::tracing::debug!(signal = self.name(), pending = ?pending, "exit resolved"),
+            None => ::tracing::debug!(signal = self.name(), "exit resolved"),
+        }
+    }
+
+    fn fire_result(&self) -> Result<usize, FireError> {
+        match self.listeners.load(Ordering::Acquire) {
+            0 => Err(FireError::NoListeners),
+            n => Ok(n),
+        }
+    }
+
+    // completes a fire claimed by `try_set`, unless another thread holds
+    // the lock: that thread does once it releases it. the fence pairs with
+    // the one in `Locked::drop`, so one of the two sees the other.
+    fn help(&self) {
+        atomic::fence(Ordering::SeqCst);
+        while self.state.load(Ordering::Acquire) & CLAIMED != 0 {
+            let mut waiting = match self.waiting.try_lock() {
+                Some(waiting) => waiting,
+                None => return,
+            };
+            let wake_up = self.complete(&mut waiting);
+            drop(waiting);
+            drop(wake_up);
+        }
+    }
+
+    // fires with the reason stashed by `try_set`, if it claimed the fire.
+    fn complete(&self, waiting: &mut Waiting<T>) -> WakeUp {
+        let state = self.state.load(Ordering::Acquire);
+        if state & CLAIMED == 0 {
+            return WakeUp(Vec::new())
+        }
+
+        // stashed before claiming, and only taken here, under the lock.
+        let reason = unsafe { Box::from_raw(self.pending.swap(ptr::null_mut(), Ordering::AcqRel)) };
+        // nothing else changes the state while it's claimed.
+        self.state.store((state & !CLAIMED) | FIRED, Ordering::Release);
+        self.fired(waiting, *reason)
+    }
+
+    // runs the hooks once the state is fired. returns the tasks to wake
+    // once the lock is released.
+    fn fired(&self, waiting: &mut Waiting<T>, reason: Option<T>) -> WakeUp {
+        waiting.reason = reason;
+        #[cfg(feature = "std")]
+        {
+            waiting.fired_at = Some(Instant::now());
+        }
+        #[cfg(feature = "blocking")]
+        self.blocked.notify_all();
+
+        // slots stay registered so exits are still woken after a reset.
+        // collected before running the hooks, so the exits are still woken
+        // if one of them panics.
+        let wake_up = WakeUp(waiting.tasks.values().cloned().collect());
+        self.run_hooks(waiting, |waiting| {
+            let Waiting { ref mut hooks, ref reason, .. } = *waiting;
+            hooks.retain(|_, hook| hook(reason.as_ref()));
+        });
+
+        wake_up
+    }
+
+    #[cfg(feature = "blocking")]
+    // blocks the current thread until `fired` holds for the state or the
+    // deadline, if any, passes. returns whether it held.
+    fn wait_until<F: Fn(usize) -> bool>(&self, fired: F, deadline: Option<Instant>) -> bool {
+        // only waits that block are reported.
+        #[cfg(feature = "tracing")]
+        let start = match fired(self.state.load(Ordering::Acquire)) {
+            true => None,
+            false => Some(Instant::now()),
+        };
+
+        let done = self.block_until(|| fired(self.state.load(Ordering::Acquire)), deadline);
+        #[cfg(feature = "tracing")]
+        if let (true, Some(start)) = (done, start) {
+            self.report_resolved(Some(start.elapsed()));
+        }
+        done
+    }
+
+    #[cfg(feature = "blocking")]
+    // blocks the current thread until there are no listeners or the
+    // deadline, if any, passes. returns whether it became idle.
+    fn wait_idle_until(&self, deadline: Option<Instant>) -> bool {
+        self.block_until(|| self.is_idle(), deadline)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn block_until<F: Fn() -> bool>(&self, done: F, deadline: Option<Instant>) -> bool {
+        let mut waiting = self.lock();
+        loop {
+            if self.driven.load(Ordering::Acquire) {
+                self.woken.store(false, Ordering::Release);
+                MutexGuard::unlocked(waiting.guard(), || self.drive());
+            }
+
+            if done() {
+                break
+            }
+            // notified while polling, with the lock released.
+            if self.woken.swap(false, Ordering::AcqRel) {
+                continue
+            }
+
+            match deadline {
+                Some(deadline) => if self.blocked.wait_until(waiting.guard(), deadline).timed_out() {
+                    break
+                },
+                None => self.blocked.wait(waiting.guard()),
+            }
+        }
+
+        done()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.listener_count() == 0
+    }
+
+    fn listener_count(&self) -> usize {
+        self.listeners.load(Ordering::Acquire)
+    }
+
+    fn waiting_count(&self) -> usize {
+        self.lock().tasks.len()
+    }
+
+    fn release_listener(&self) {
+        let last = self.listeners.fetch_sub(1, Ordering::AcqRel) == 1;
+        #[cfg(feature = "std")]
+        let metrics = self.metrics.get();
+        #[cfg(feature = "std")]
+        if let Some(metrics) = metrics {
+            metrics.listener_deregistered();
+        }
+
+        if !last {
+            return
+        }
+
+        #[allow(unused_mut)]
+        let mut waiting = self.lock();
+        #[cfg(feature = "blocking")]
+        self.blocked.notify_all();
+        #[cfg(feature = "std")]
+        let fired_at = waiting.fired_at.take();
+        let wake_up = waiting.idle.values().cloned().collect::<Vec<_>>();
+        drop(waiting);
+
+        for task in wake_up {
+            task.notify()
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(fired_at) = fired_at {
+            let duration = fired_at.elapsed();
+            if let Some(metrics) = metrics {
+                metrics.drained(duration);
+            }
+            #[cfg(feature = "tracing")]
+            ::tracing::debug!(signal = self.name(), after = ?duration, "exits drained");
+        }
+    }
+
+    fn register_idle(&self, task: Arc<AtomicTask>) -> usize {
+        let id = self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = self.lock().idle.insert(id, task);
+
+        id
+    }
+
+    fn is_live(&self) -> bool {
+        !is_fired(self.state.load(Ordering::Acquire))
+    }
+
+    // registers a wakeup slot to be notified on fire. returns its id.
+    fn register(&self, task: Arc<AtomicTask>) -> usize {
+        let id = self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = self.lock().tasks.insert(id, task);
+
+        id
+    }
+
+    // returns whether the signal was fired.
+    fn reset(&self) -> bool {
+        let mut waiting = self.lock();
+        // a fire claimed since taking the lock comes before the reset.
+        waiting.complete();
+        let state = self.state.load(Ordering::Acquire);
+        if state & FIRED == 0 {
+            return false
+        }
+
+        // the reason is kept for exits yet to observe the fire.
+        #[cfg(feature = "std")]
+        {
+            waiting.fired_at = None;
+        }
+        self.state.store((generation(state) + 1) << 2, Ordering::Release);
+        true
+    }
+
+    // runs the hook on every fire, starting immediately if already fired.
+    // returns its id, or `None` if it ran and asked not to be kept.
+    fn add_hook(&self, mut hook: Hook<T>) -> Option<usize> {
+        let mut waiting = self.lock();
+        if !self.is_live() && !self.run_hooks(&mut waiting, |waiting| hook(waiting.reason.as_ref())) {
+            return None
+        }
+
+        let id = self.count.fetch_add(1, Ordering::Relaxed);
+        let _ = waiting.hooks.insert(id, hook);
+        Some(id)
+    }
+
+    // like `add_hook`, but unregistering the hook once the guard is dropped.
+    fn hook(this: &Arc<Shared<T>>, hook: Hook<T>) -> FireHook where T: Send + 'static {
+        let shared: Weak<Shared<T>> = Arc::downgrade(this);
+        let shared: Weak<dyn Unhook + Send + Sync> = shared;
+        FireHook { hook: this.add_hook(hook).map(|id| (shared, id)) }
+    }
+
+    // runs hooks with the lock held, then drops the ones removed meanwhile.
+    fn run_hooks<R, F>(&self, waiting: &mut Waiting<T>, f: F) -> R where F: FnOnce(&mut Waiting<T>) -> R {
+        struct Running<'a>(&'a AtomicBool);
+
+        impl<'a> Drop for Running<'a> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::SeqCst);
+            }
+        }
+
+        self.running_hooks.store(true, Ordering::SeqCst);
+        let running = Running(&self.running_hooks);
+        let res = f(waiting);
+        drop(running);
+
+        for id in self.unhooked.lock().drain(..) {
+            let _ = waiting.hooks.remove(&id);
+        }
+        res
+    }
+
+    fn deregister(&self, id: usize) {
+        let _ = self.lock().tasks.remove(&id);
+    }
+}
+
+// removes hooks through a `FireHook`, whatever the reason type.
+trait Unhook {
+    fn unhook(&self, id: usize);
+}
+
+impl<T> Unhook for Shared<T> {
+    fn unhook(&self, id: usize) {
+        // a hook may drop the last handle to a hook of its own signal, such
+        // as a child's, while its lock is held. leave the removal to the
+        // thread running the hooks then.
+        if !self.running_hooks.load(Ordering::SeqCst) {
+            let _ = self.lock().hooks.remove(&id);
+            return
+        }
+
+        self.unhooked.lock().push(id);
+        // the hooks may have finished running before the id was pushed.
+        if !self.running_hooks.load(Ordering::SeqCst) {
+            let mut waiting = self.lock();
+            for id in self.unhooked.lock().drain(..) {
+                let _ = waiting.hooks.remove(&id);
+            }
+        }
+    }
+}
+
+/// An action registered to run when a signal fires, such as the one made
+/// by `Exit::abort_on_exit`.
+///
+/// Dropping it unregisters the action, so signals that never fire don't
+/// accumulate actions nothing cares about anymore.
+#[must_use = "the action is unregistered once this is dropped"]
+pub struct FireHook {
+    hook: Option<(Weak<dyn Unhook + Send + Sync>, usize)>,
+}
+
+impl FireHook {
+    /// Keep the action registered for as long as the signal lives.
+    pub fn forget(mut self) {
+        self.hook = None;
+    }
+}
+
+impl Drop for FireHook {
+    fn drop(&mut self) {
+        if let Some((ref shared, id)) = self.hook {
+            if let Some(shared) = shared.upgrade() {
+                shared.unhook(id);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for FireHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FireHook").field("registered", &self.hook.is_some()).finish()
+    }
+}
+
+// frees a reason stashed by a `try_fire` that was never completed.
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let pending = *self.pending.get_mut();
+        if !pending.is_null() {
+            drop(unsafe { Box::from_raw(pending) });
+        }
+    }
+}
+
+impl<T: Clone + Default> Shared<T> {
+    // should be called only once fired.
+    fn reason(&self) -> T {
+        self.lock().reason.clone().unwrap_or_default()
+    }
+}
+
+/// What dropping a `Signal` handle does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropBehavior {
+    /// Fire whenever this handle is dropped.
+    FireOnDrop,
+    /// Fire only when this is the last armed handle to be dropped.
+    #[default]
+    FireOnLastDrop,
+}
+
+/// Exit signal that fires either manually or on drop.
+///
+/// Clones fire the same exits and inherit the drop behavior of the handle
+/// they were cloned from.
+pub struct Signal<T = ()> {
+    shared: Arc<Shared<T>>,
+    // whether to fire on drop.
+    armed: bool,
+    on_drop: DropBehavior,
+}
+
+impl<T> Signal<T> {
+    /// Create a signal that isn't connected to anything, for components
+    /// that need one when nothing cares about their lifecycle.
+    ///
+    /// Neither it nor its clones fire when dropped, and firing it only
+    /// reaches exits made from it.
+    pub fn dummy() -> Signal<T> {
+        // never disarmed, so the count of armed handles never drops to zero.
+        Signal { shared: Arc::new(Shared::new()), armed: false, on_drop: DropBehavior::default() }
+    }
+
+    /// Fire the signal manually, resolving exits to `reason`.
+    ///
+    /// This never blocks on or drives an executor: it only marks the signal
+    /// as fired and notifies waiting tasks, so it's safe to call from within
+    /// a task, including one that is itself waiting on an `Exit`.
+    ///
+    /// Returns the number of `Exit` handles that were alive and notified.
+    /// Fails if the signal was already fired, or if no `Exit` was alive to
+    /// observe it.
+    ///
+    /// Any number of threads may race to fire the same signal, through any
+    /// of its handles or by dropping them. Exactly one of them fires it:
+    /// that call returns `Ok` or `FireError::NoListeners` and its reason is
+    /// the one every exit resolves to, while the rest fail with
+    /// `FireError::AlreadyFired`. Every exit alive at the time is woken
+    /// before the winning call returns.
+    pub fn fire_with(&self, reason: T) -> Result<usize, FireError> {
+        self.shared.set(Some(reason))
+    }
+
+    /// Fire the signal like `fire_with`, but without ever waiting on a lock.
+    ///
+    /// The signal is fired once this returns, with the same results as
+    /// `fire_with`. If another thread holds the signal's lock, for instance
+    /// while registering with it, that thread stores the reason, wakes the
+    /// exits and runs the hooks as soon as it releases the lock, so this
+    /// call doesn't wait for it.
+    pub fn try_fire_with(&self, reason: T) -> Result<usize, FireError> {
+        self.shared.try_set(Some(reason))
+    }
+
+    /// Fire the signal, returning a future which resolves once every `Exit`
+    /// has acknowledged it by being dropped, for instance through
+    /// `Exit::ack`.
+    ///
+    /// If the signal hasn't fired yet, exits resolve as if it was dropped;
+    /// use `fire_with` first to give a reason.
+    pub fn fire_and_wait(&self) -> Idle<T> {
+        let _ = self.shared.set(None);
+        self.wait_idle()
+    }
+
+    /// Fire the signal and block the current thread until every `Exit` has
+    /// been dropped or `timeout` has elapsed, returning whether they were.
+    #[cfg(feature = "blocking")]
+    pub fn fire_and_wait_timeout(&self, timeout: Duration) -> bool {
+        let _ = self.shared.set(None);
+        self.wait_idle_timeout(timeout)
+    }
+
+    /// Get a future which resolves once every `Exit` has been dropped,
+    /// without firing the signal.
+    pub fn wait_idle(&self) -> Idle<T> {
+        Idle { shared: self.shared.clone(), slot: None }
+    }
+
+    /// Block the current thread until every `Exit` has been dropped or
+    /// `timeout` has elapsed, returning whether they were.
+    #[cfg(feature = "blocking")]
+    pub fn wait_idle_timeout(&self, timeout: Duration) -> bool {
+        self.shared.wait_idle_until(Instant::now().checked_add(timeout))
+    }
+
+    /// The number of live exits for this signal. See `Exit::listener_count`.
+    pub fn listener_count(&self) -> usize {
+        self.shared.listener_count()
+    }
+
+    /// Whether the signal has already been fired.
+    pub fn is_fired(&self) -> bool {
+        !self.shared.is_live()
+    }
+
+    /// Re-arm a fired signal, returning whether it had been fired.
+    ///
+    /// This starts a new generation of the signal. Existing exits, including
+    /// ones that already resolved, go back to waiting for it to fire again.
+    /// An exit that hadn't observed the fire before the reset still
+    /// resolves once, with that fire's reason, before it waits for the next.
+    pub fn reset(&self) -> bool {
+        self.shared.reset()
+    }
+
+    /// Create a child signal, which fires along with this one but can also
+    /// be fired on its own without affecting this signal or its other
+    /// children.
+    ///
+    /// The child starts out fired if this signal already is. Once every
+    /// handle to the child is gone, this signal forgets about it.
+    pub fn child(&self) -> Signal<T> where T: Clone + Send + Sync + 'static {
+        let child = signal_only_with::<T>();
+        let weak = Arc::downgrade(&child.shared);
+
+        let hook = Shared::hook(&self.shared, Box::new(move |reason| match weak.upgrade() {
+            Some(child) => {
+                let _ = child.set(reason.cloned());
+                true
+            }
+            None => false,
+        }));
+        *child.shared.parent.lock() = Some(hook);
+
+        child
+    }
+
+    /// Consume the signal without firing it.
+    ///
+    /// Exits only resolve afterwards if the signal was already fired or
+    /// another handle fires it.
+    pub fn defuse(mut self) {
+        self.disarm();
+    }
+
+    /// Set what dropping this handle does. Defaults to
+    /// `DropBehavior::FireOnLastDrop`.
+    pub fn with_drop_behavior(mut self, on_drop: DropBehavior) -> Signal<T> {
+        self.on_drop = on_drop;
+        self
+    }
+
+    // returns whether this was the last armed handle.
+    fn disarm(&mut self) -> bool {
+        self.armed = false;
+        self.shared.signals.fetch_sub(1, Ordering::AcqRel) == 1
+    }
+
+    /// Install hooks observing this signal's lifecycle. Exits made before
+    /// they are installed aren't reported as registered.
+    ///
+    /// Only the first metrics installed through any of the signal's handles
+    /// are kept.
+    #[cfg(feature = "std")]
+    pub fn with_metrics<M: Metrics + 'static>(self, metrics: M) -> Signal<T> {
+        let _ = self.shared.metrics.set(Box::new(metrics));
+        self
+    }
+
+    /// Name the signal in the tracing events it emits: when it fires, when
+    /// its exits resolve and how long they were pending, and when they have
+    /// all been dropped after the fire.
+    ///
+    /// Only the first name given through any of the signal's handles is
+    /// kept. Unnamed signals are reported as `unnamed`.
+    #[cfg(feature = "tracing")]
+    pub fn with_name<N: Into<Cow<'static, str>>>(self, name: N) -> Signal<T> {
+        let _ = self.shared.name.set(name.into());
+        self
+    }
+
+    /// Get an exit future.
+    pub fn make_exit(&self) -> Exit<T> {
+        Exit::new(self.shared.clone())
+    }
+
+    /// Get a weak handle which can fire the signal, but neither keeps it
+    /// from firing on drop nor fires when dropped itself.
+    pub fn downgrade(&self) -> WeakSignal<T> {
+        WeakSignal { shared: Arc::downgrade(&self.shared) }
+    }
+}
+
+impl<T: Default> Signal<T> {
+    /// Fire the signal manually with the default reason. See `fire_with`.
+    pub fn fire(&self) -> Result<usize, FireError> {
+        self.fire_with(T::default())
+    }
+
+    /// Fire the signal with the default reason, like `fire`, returning a
+    /// future which resolves with the number of exits notified once each of
+    /// them has observed the fire by being dropped, for instance through
+    /// `Exit::ack`.
+    ///
+    /// The signal fires right away, without blocking, so this can be called
+    /// from within a task that goes on to await the result. The future fails
+    /// as `fire` would have.
+    pub fn fire_async(&self) -> FireAsync<T> {
+        FireAsync { fired: Some(self.fire()), idle: self.wait_idle() }
+    }
+
+    /// Fire the signal with the default reason without ever waiting on a
+    /// lock. See `try_fire_with`.
+    pub fn try_fire(&self) -> Result<usize, FireError> {
+        self.try_fire_with(T::default())
+    }
+
+    /// Fire the signal with the default reason once `delay` has elapsed,
+    /// unless the returned handle is defused first.
+    ///
+    /// The fire is scheduled through a weak handle, so it doesn't keep the
+    /// signal from firing on drop in the meantime.
+    #[cfg(feature = "timer")]
+    pub fn fire_after(&self, delay: Duration) -> DelayedFire where T: Send + Sync + 'static {
+        let done = Arc::new(AtomicBool::new(false));
+
+        // a delay too large to represent never elapses.
+        if let Some(at) = Instant::now().checked_add(delay) {
+            let signal = self.downgrade();
+            let timer_done = done.clone();
+            timer::schedule(at, move || if !timer_done.swap(true, Ordering::AcqRel) {
+                let _ = signal.fire();
+            });
+        }
+
+        DelayedFire { done }
+    }
+}
+
+/// Handle to a fire scheduled by `Signal::fire_after`.
+///
+/// Dropping it leaves the fire scheduled.
+#[cfg(feature = "timer")]
+#[derive(Debug)]
+pub struct DelayedFire {
+    // set once the signal has been fired or the fire cancelled.
+    done: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "timer")]
+impl DelayedFire {
+    /// Cancel the scheduled fire, returning whether it hadn't happened yet.
+    pub fn defuse(self) -> bool {
+        !self.done.swap(true, Ordering::AcqRel)
+    }
+}
+
+// firing on drop is best-effort and never panics, so dropping a `Signal`
+// while unwinding can't turn a panic into an abort.
+impl<T> Drop for Signal<T> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return
+        }
+
+        let last = self.disarm();
+        if last || self.on_drop == DropBehavior::FireOnDrop {
+            let _ = self.shared.set(None);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Signal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Signal")
+            .field("fired", &!self.shared.is_live())
+            .field("listeners", &self.shared.listeners.load(Ordering::Relaxed))
+            .field("armed", &self.armed)
+            .field("on_drop", &self.on_drop)
+            .finish()
+    }
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Signal<T> {
+        self.shared.signals.fetch_add(1, Ordering::Relaxed);
+        Signal { shared: self.shared.clone(), armed: true, on_drop: self.on_drop }
+    }
+}
+
+// see the impls for `Exit`. firing moves a reason into the shared state.
+impl<T: UnwindSafe + RefUnwindSafe> UnwindSafe for Signal<T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for Signal<T> {}
+
+/// Weak handle to a `Signal`, obtained through `Signal::downgrade`.
+pub struct WeakSignal<T = ()> {
+    shared: Weak<Shared<T>>,
+}
+
+impl<T> WeakSignal<T> {
+    /// Fire the signal, like `Signal::fire_with`.
+    ///
+    /// Fails with `FireError::NoListeners` once every `Signal` and `Exit`
+    /// handle is gone.
+    pub fn fire_with(&self, reason: T) -> Result<usize, FireError> {
+        match self.shared.upgrade() {
+            Some(shared) => shared.set(Some(reason)),
+            None => Err(FireError::NoListeners),
+        }
+    }
+}
+
+impl<T: Default> WeakSignal<T> {
+    /// Fire the signal with the default reason, like `Signal::fire`.
+    pub fn fire(&self) -> Result<usize, FireError> {
+        self.fire_with(T::default())
+    }
+}
+
+impl<T> fmt::Debug for WeakSignal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shared = self.shared.upgrade();
+        f.debug_struct("WeakSignal")
+            .field("alive", &shared.is_some())
+            .field("fired", &shared.map(|shared| !shared.is_live()))
+            .finish()
+    }
+}
+
+impl<T> Clone for WeakSignal<T> {
+    fn clone(&self) -> WeakSignal<T> {
+        WeakSignal { shared: self.shared.clone() }
+    }
+}
+
+impl<T: Clone + Default + Send + Sync + 'static> SignalHandle for Signal<T> {
+    type Reason = T;
+    type Exit = Exit<T>;
+
+    fn fire_with(&self, reason: T) -> Result<usize, FireError> {
+        Signal::fire_with(self, reason)
+    }
+
+    fn make_exit(&self) -> Exit<T> {
+        Signal::make_exit(self)
+    }
+
+    fn is_fired(&self) -> bool {
+        Signal::is_fired(self)
+    }
+
+    fn listener_count(&self) -> usize {
+        Signal::listener_count(self)
+    }
+
+    fn reset(&self) -> bool {
+        Signal::reset(self)
+    }
+
+    fn child(&self) -> Signal<T> {
+        Signal::child(self)
+    }
+
+    fn defuse(self) {
+        Signal::defuse(self)
+    }
+
+    fn with_drop_behavior(self, on_drop: DropBehavior) -> Signal<T> {
+        Signal::with_drop_behavior(self, on_drop)
+    }
+}
+
+impl<T: Clone + Default> ExitHandle for Exit<T> {
+    fn is_live(&self) -> bool {
+        Exit::is_live(self)
+    }
+
+    fn listener_count(&self) -> usize {
+        Exit::listener_count(self)
+    }
+}
+
+/// Create a signal and exit pair. `Exit` is a future that resolves when the
+/// `Signal` object is either dropped or has `fire` called on it.
+pub fn signal() -> (Signal, Exit) {
+    signal_with()
+}
+
+/// Create only a signal.
+pub fn signal_only() -> Signal {
+    signal_only_with()
+}
+
+/// Create a signal and exit pair where the exit resolves to the reason the
+/// signal was fired with.
+pub fn signal_with<T>() -> (Signal<T>, Exit<T>) {
+    let signal = signal_only_with();
+    let exit = signal.make_exit();
+
+    (signal, exit)
+}
+
+/// Create only a signal carrying a reason.
+pub fn signal_only_with<T>() -> Signal<T> {
+    let shared = Arc::new(Shared::new());
+
+    Signal { shared, armed: true, on_drop: DropBehavior::default() }
+}
+
+#[cfg(feature = "std")]
+static GLOBAL: OnceLock<Signal> = OnceLock::new();
+
+/// Get the process-wide signal and exit, created on first use.
+///
+/// The process holds a handle of its own, so dropping the returned signal
+/// never fires it. Libraries should only observe it through `global_exit`,
+/// leaving the binary to fire it.
+#[cfg(feature = "std")]
+pub fn global() -> (Signal, Exit) {
+    let signal = GLOBAL.get_or_init(signal_only);
+    (signal.clone(), signal.make_exit())
+}
+
+/// Get an exit for the process-wide signal. See `global`.
+#[cfg(feature = "std")]
+pub fn global_exit() -> Exit {
+    GLOBAL.get_or_init(signal_only).make_exit()
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let (signal, exit_a) = signal();
+        let exit_b = exit_a.clone();
+        let exit_c = exit_b.clone();
+
+        assert!(exit_a.is_live() && exit_b.is_live());
+
+        let barrier = Arc::new(::std::sync::Barrier::new(2));
+        let thread_barrier = barrier.clone();
+        let handle = ::std::thread::spawn(move || {
+            let barrier = ::futures::future::lazy(move || { thread_barrier.wait(); Ok(()) });
+
+            assert!(exit_a.join3(exit_b, barrier).wait().is_ok());
+        });
+
+        barrier.wait();
+        signal.fire().unwrap();
+
+        let _ = handle.join();
+        assert!(!exit_c.is_live());
+        assert!(exit_c.wait().is_ok());
+    }
+
+    #[test]
+    fn exit_signal_are_send_and_sync() {
+        fn is_send_and_sync<T: Send + Sync>() {}
+
+        is_send_and_sync::<Exit>();
+        is_send_and_sync::<Signal>();
+    }
+
+    #[test]
+    fn until_prefers_ready_work() {
+        let (signal, exit) = signal();
+        signal.fire().unwrap();
+
+        assert_eq!(exit.clone().until(future::ok::<_, ()>(5)).wait(), Ok(Some(5)));
+        assert_eq!(exit.clone().until_result(future::ok::<_, ()>(5)).wait(), Ok(Ok(5)));
+        match exit.clone().until_keep(future::ok::<_, ()>(5)).wait() {
+            Ok(Either::A(5)) => {}
+            _ => panic!("ready work was discarded"),
+        }
+        assert_eq!(exit.until(future::err::<(), _>(3)).wait(), Err(3));
+    }
+
+    #[test]
+    fn work_until() {
+        let (signal, exit) = signal();
+        let work_a = exit.clone().until(future::ok::<_, ()>(5));
+        assert_eq!(work_a.wait().unwrap(), Some(5));
+
+        signal.fire().unwrap();
+        let work_b = exit.until(::futures::future::empty::<(), ()>());
+        assert_eq!(work_b.wait().unwrap(), None);
+    }
+
+    #[test]
+    fn work_until_exit() {
+        let (signal, exit) = signal();
+        let work_a = future::ok::<_, ()>(5).map(|x| x + 1).until_exit(&exit);
+        assert_eq!(work_a.wait(), Ok(Some(6)));
+
+        signal.fire().unwrap();
+        let work_b = future::empty::<(), ()>().until_exit(&exit);
+        assert_eq!(work_b.wait(), Ok(None));
+        assert!(exit.wait().is_ok());
+    }
+
+    #[test]
+    fn work_until_result() {
+        let (signal, exit) = signal();
+        let work_a = exit.clone().until_result(future::ok::<_, ()>(5));
+        assert_eq!(work_a.wait(), Ok(Ok(5)));
+
+        signal.fire().unwrap();
+        let work_b = exit.until_result(future::empty::<(), ()>());
+        assert_eq!(work_b.wait(), Ok(Err(Cancelled)));
+        assert_eq!(Cancelled.to_string(), "cancelled by exit signal");
+    }
+
+    #[test]
+    fn clone_after_fire() {
+        let (signal, exit) = signal_with::<u32>();
+        signal.fire_with(7).unwrap();
+
+        let mut late = exit.clone();
+        let mut made = signal.make_exit();
+        future::lazy(move || {
+            assert_eq!(late.poll(), Ok(Async::Ready(7)));
+            assert_eq!(made.poll(), Ok(Async::Ready(7)));
+            assert_eq!(late.clone().poll(), Ok(Async::Ready(7)));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+
+        drop(signal);
+        assert_eq!(exit.clone().wait(), Ok(7));
+    }
+
+    #[test]
+    fn clone_inside_poll() {
+        let (signal, mut exit) = signal();
+
+        future::poll_fn(move || {
+            assert_eq!(exit.poll(), Ok(Async::NotReady));
+
+            // clones made while polling, before and after the fire.
+            let mut early = exit.clone();
+            assert_eq!(early.poll(), Ok(Async::NotReady));
+            signal.fire().unwrap();
+            assert_eq!(early.poll(), Ok(Async::Ready(())));
+            assert_eq!(exit.clone().poll(), Ok(Async::Ready(())));
+
+            exit.poll()
+        }).wait().unwrap();
+    }
+
+    // counts the times the signal fires.
+    fn count_fires<T>(exit: &Exit<T>) -> Arc<AtomicUsize> {
+        let fires = Arc::new(AtomicUsize::new(0));
+        let counter = fires.clone();
+        exit.shared.add_hook(Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            true
+        }));
+
+        fires
+    }
+
+    #[test]
+    fn concurrent_fire() {
+        use std::sync::Barrier;
+        use std::thread;
+
+        for _ in 0..50 {
+            let (signal, exit) = signal_with::<usize>();
+            let fires = count_fires(&exit);
+            let barrier = Arc::new(Barrier::new(16));
+
+            let waiters = (0..8).map(|_| {
+                let exit = exit.clone();
+                thread::spawn(move || exit.wait())
+            }).collect::<Vec<_>>();
+            let firers = (0..16).map(|i| {
+                let signal = signal.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    (i, signal.fire_with(i))
+                })
+            }).collect::<Vec<_>>();
+
+            let mut winners = firers.into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(|(_, res)| *res != Err(FireError::AlreadyFired));
+            let (winner, res) = winners.next().unwrap();
+            assert!(res.is_ok());
+            assert!(winners.next().is_none());
+
+            for waiter in waiters {
+                assert_eq!(waiter.join().unwrap(), Ok(winner));
+            }
+            assert_eq!(exit.wait(), Ok(winner));
+            assert_eq!(fires.load(Ordering::SeqCst), 1);
+            drop(signal);
+            assert_eq!(fires.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn concurrent_clone_and_drop() {
+        use std::sync::Barrier;
+        use std::thread;
+
+        for _ in 0..50 {
+            let (signal, exit) = signal();
+            let fires = count_fires(&exit);
+            let barrier = Arc::new(Barrier::new(9));
+
+            // every handle is dropped while others are still being cloned,
+            // so only the last drop may fire.
+            let handles = (0..8).map(|_| {
+                let signal = signal.clone();
+                let exit = exit.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..100 {
+                        drop(signal.clone());
+                        drop(exit.clone());
+                    }
+                    assert!(exit.is_live());
+                })
+            }).collect::<Vec<_>>();
+            barrier.wait();
+            drop(signal);
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            assert_eq!(exit.wait(), Ok(()));
+            assert_eq!(fires.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    #[test]
+    fn unwind_safe_bounds() {
+        fn unwind_safe<T: UnwindSafe + RefUnwindSafe>() {}
+
+        unwind_safe::<Exit<u32>>();
+        unwind_safe::<Signal<u32>>();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn unwind_safe() {
+        use std::panic;
+        use std::thread;
+
+        let (signal, exit) = signal();
+        let (tx, rx) = ::std::sync::mpsc::channel();
+        let clone = exit.clone();
+        let handle = thread::spawn(move || {
+            let mut clone = clone;
+            future::poll_fn(|| {
+                let ready = clone.poll();
+                let _ = tx.send(());
+                ready
+            }).wait()
+        });
+        rx.recv().unwrap();
+
+        exit.shared.add_hook(Box::new(|_| panic!("hook failed")));
+        assert!(panic::catch_unwind(|| signal.fire()).is_err());
+
+        // the exits were still woken.
+        assert!(handle.join().unwrap().is_ok());
+        assert!(panic::catch_unwind(|| exit.is_live()).is_ok_and(|live| !live));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn global_pair() {
+        let (signal, exit) = global();
+        assert!(Arc::ptr_eq(&exit.shared, &global_exit().shared));
+
+        // the process keeps its own handle, so this can't fire it.
+        drop(signal);
+        assert!(exit.is_live());
+    }
+
+    #[test]
+    fn exit_as_flag() {
+        let (signal, exit) = signal();
+        let flag = exit.as_flag();
+        assert!(!flag.is_set());
+
+        signal.fire().unwrap();
+        assert!(flag.is_set());
+        assert!(flag.clone().load(Ordering::Relaxed));
+        assert!(!exit.is_live());
+
+        signal.reset();
+        assert!(!flag.is_set());
+        assert!(exit.is_live());
+
+        // the flag isn't a listener.
+        drop(exit);
+        assert_eq!(signal.fire(), Err(FireError::NoListeners));
+        assert!(flag.is_set());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn exit_as_mpsc_receiver() {
+        let (signal, exit) = signal();
+        let rx = exit.as_mpsc_receiver();
+        assert!(rx.recv_timeout(Duration::from_millis(10)).is_err());
+
+        signal.fire().unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Ok(()));
+        assert!(rx.recv().is_err());
+        assert_eq!(exit.as_mpsc_receiver().recv(), Ok(()));
+
+        signal.reset();
+        drop(exit.as_mpsc_receiver());
+        assert!(exit.shared.waiting.lock().hooks.is_empty());
+    }
+
+    #[test]
+    fn recv_oneshot_until_exit() {
+        let (signal, exit) = signal();
+        let (tx, rx) = ::futures::sync::oneshot::channel::<u32>();
+        tx.send(5).unwrap();
+        assert_eq!(rx.recv_until_exit(&exit).wait(), Ok(Ok(5)));
+
+        let (_tx, rx) = ::futures::sync::oneshot::channel::<u32>();
+        signal.fire().unwrap();
+        assert_eq!(rx.recv_until_exit(&exit).wait(), Ok(Err(Cancelled)));
+    }
+
+    #[test]
+    #[cfg(feature = "timer")]
+    fn exit_or_after() {
+        let (signal, exit) = signal();
+        let deadline = exit.clone().or_after(Duration::from_millis(20));
+        assert_eq!(deadline.wait(), Ok(None));
+
+        let deadline = exit.or_after(Duration::from_millis(5000));
+        signal.fire().unwrap();
+        assert_eq!(deadline.wait(), Ok(Some(())));
+    }
+
+    #[test]
+    #[cfg(feature = "timer")]
+    fn exit_delay() {
+        let (signal, exit) = signal();
+        assert_eq!(exit.delay(Duration::from_millis(20)).wait(), Ok(true));
+
+        let start = Instant::now();
+        let sleep = exit.delay(Duration::from_millis(5000));
+        signal.fire().unwrap();
+        assert_eq!(sleep.wait(), Ok(false));
+        assert!(start.elapsed() < Duration::from_millis(5000));
+    }
+
+    #[test]
+    #[cfg(feature = "timer")]
+    fn exit_delay_overflow() {
+        let (signal, exit) = signal();
+        let mut sleep = exit.delay(Duration::MAX);
+        let mut deadline = exit.or_after(Duration::MAX);
+        future::lazy(move || {
+            assert!(sleep.poll().unwrap().is_not_ready());
+            assert!(deadline.poll().unwrap().is_not_ready());
+            signal.fire().unwrap();
+            assert_eq!(sleep.poll(), Ok(Async::Ready(false)));
+            assert_eq!(deadline.poll(), Ok(Async::Ready(Some(()))));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "timer")]
+    fn fire_after_delay() {
+        let (signal, exit) = signal();
+        let delayed = signal.fire_after(Duration::from_millis(20));
+        assert!(exit.is_live());
+        assert_eq!(Future::wait(exit), Ok(()));
+        assert!(!delayed.defuse());
+
+        signal.reset();
+        let exit = signal.make_exit();
+        assert!(signal.fire_after(Duration::from_millis(20)).defuse());
+        assert_eq!(exit.or_after(Duration::from_millis(50)).wait(), Ok(None));
+    }
+
+    #[test]
+    #[cfg(feature = "timer")]
+    fn work_until_graceful() {
+        let (signal, exit) = signal();
+        signal.fire().unwrap();
+
+        let (tx, rx) = ::futures::sync::oneshot::channel::<u32>();
+        let grace = Duration::from_millis(5000);
+        let work_a = exit.clone().until_graceful(rx.map_err(|_| ()), grace);
+        ::std::thread::spawn(move || {
+            ::std::thread::sleep(Duration::from_millis(50));
+            tx.send(5).unwrap();
+        });
+        assert_eq!(work_a.wait(), Ok(Some(5)));
+
+        let start = Instant::now();
+        let grace = Duration::from_millis(50);
+        let work_b = exit.clone().until_graceful(future::empty::<(), ()>(), grace);
+        assert_eq!(work_b.wait(), Ok(None));
+        assert!(start.elapsed() >= grace);
+
+        let (tx, rx) = ::futures::sync::oneshot::channel::<u32>();
+        let work_c = exit.until_graceful(rx.map_err(|_| ()), Duration::MAX);
+        ::std::thread::spawn(move || {
+            ::std::thread::sleep(Duration::from_millis(50));
+            tx.send(6).unwrap();
+        });
+        assert_eq!(work_c.wait(), Ok(Some(6)));
+    }
+
+    #[test]
+    fn work_until_with_cleanup() {
+        let cleaned = Arc::new(AtomicBool::new(false));
+        let (signal, exit) = signal();
+
+        let flag = cleaned.clone();
+        let work_a = exit.clone().until_with_cleanup(future::ok::<_, ()>(5), move || {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        assert_eq!(work_a.wait(), Ok(Some(5)));
+        assert!(!cleaned.load(Ordering::SeqCst));
+
+        signal.fire().unwrap();
+        let flag = cleaned.clone();
+        let work_b = exit.until_with_cleanup(future::empty::<(), ()>(), move || {
+            future::lazy(move || { flag.store(true, Ordering::SeqCst); Ok(()) })
+        });
+        assert_eq!(work_b.wait(), Ok(None));
+        assert!(cleaned.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn work_until_keep() {
+        let (signal, exit) = signal();
+        let work_a = exit.clone().until_keep(future::ok::<_, ()>(5));
+        match work_a.wait() {
+            Ok(Either::A(5)) => {}
+            _ => panic!("work should have completed"),
+        }
+
+        signal.fire().unwrap();
+        let work_b = exit.until_keep(future::ok::<_, ()>(6).and_then(|x| Ok(x + 1)));
+        match work_b.wait() {
+            Ok(Either::A(7)) => {}
+            _ => panic!("ready work should win"),
+        }
+
+        let (signal_c, exit_c) = super::signal();
+        signal_c.fire().unwrap();
+        let work_c = exit_c.until_keep(future::empty::<u32, ()>());
+        match work_c.wait() {
+            Ok(Either::B(_)) => {}
+            _ => panic!("unfinished work should be returned"),
+        }
+    }
+
+    #[test]
+    fn works_from_other_thread() {
+        let (signal, exit) = signal();
+
+        ::std::thread::spawn(move || {
+            ::std::thread::sleep(::std::time::Duration::from_millis(2500));
+            signal.fire().unwrap();
+        });
+
+        exit.wait().unwrap();
+    }
+
+    #[test]
+    fn is_live_with_concurrent_fire() {
+        let (signal, exit) = signal();
+        let checker = exit.clone();
+
+        let handle = ::std::thread::spawn(move || {
+            while checker.is_live() {
+                ::std::thread::yield_now();
+            }
+
+            assert!(!checker.is_live());
+            checker.wait().unwrap();
+        });
+
+        signal.fire().unwrap();
+        handle.join().unwrap();
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    fn fire_from_within_task() {
+        let (signal, exit) = signal();
+        let fire = future::lazy(move || { signal.fire().unwrap(); Ok(()) });
+
+        assert!(exit.join(fire).wait().is_ok());
+    }
+
+    #[test]
+    fn fires_when_dropped_during_unwind() {
+        let (signal, exit) = signal();
+
+        let res = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(move || {
+            let _signal = signal;
+            panic!("unwinding with a live signal");
+        }));
+
+        assert!(res.is_err());
+        assert!(!exit.is_live());
+        assert!(exit.wait().is_ok());
+    }
+
+    #[test]
+    fn fire_errors() {
+        let (signal, exit) = signal();
+        assert!(!signal.is_fired());
+        assert_eq!(signal.fire(), Ok(1));
+        assert!(signal.is_fired());
+        assert_eq!(signal.fire(), Err(FireError::AlreadyFired));
+        assert!(exit.wait().is_ok());
+
+        let signal = signal_only();
+        assert_eq!(signal.fire(), Err(FireError::NoListeners));
+
+        let signal = signal_only();
+        drop(signal.make_exit());
+        assert_eq!(signal.fire(), Err(FireError::NoListeners));
+    }
+
+    #[test]
+    fn try_fire_does_not_block() {
+        let (signal, exit) = ::signal_with::<u32>();
+        let hooked = Arc::new(AtomicUsize::new(0));
+        let counter = hooked.clone();
+        let _hook = Shared::hook(&signal.shared, Box::new(move |reason: Option<&u32>| {
+            counter.store(*reason.unwrap() as usize, Ordering::SeqCst);
+            false
+        }));
+
+        let waiting = signal.shared.lock();
+        assert_eq!(signal.try_fire_with(3), Ok(1));
+        assert!(!exit.is_live());
+        assert_eq!(signal.try_fire_with(4), Err(FireError::AlreadyFired));
+        // completed by the thread holding the lock once it releases it.
+        assert_eq!(hooked.load(Ordering::SeqCst), 0);
+        drop(waiting);
+
+        assert_eq!(hooked.load(Ordering::SeqCst), 3);
+        assert_eq!(exit.wait(), Ok(3));
+    }
+
+    #[test]
+    fn try_fire_completes_when_locking() {
+        let (signal, exit) = ::signal_with::<u32>();
+        let clone = exit.clone();
+
+        // released without completing, like a thread that's still holding it.
+        let waiting = signal.shared.waiting.lock();
+        assert_eq!(signal.try_fire_with(3), Ok(2));
+        drop(waiting);
+
+        // the next lock completes it before anything reads the reason.
+        assert_eq!(exit.wait(), Ok(3));
+        assert!(signal.reset());
+        assert_eq!(signal.try_fire_with(4), Ok(1));
+        assert_eq!(clone.wait(), Ok(4));
+    }
+
+    #[test]
+    fn racing_try_fires() {
+        for _ in 0..100 {
+            let (signal, exit) = ::signal_with::<usize>();
+            let fired = Arc::new(AtomicUsize::new(0));
+            let handles: Vec<_> = (0..4).map(|n| {
+                let signal = signal.clone();
+                let fired = fired.clone();
+                let exit = exit.clone();
+                ::std::thread::spawn(move || {
+                    let res = if n % 2 == 0 { signal.try_fire_with(n) } else { signal.fire_with(n) };
+                    if res.is_ok() {
+                        fired.store(n, Ordering::SeqCst);
+                    }
+                    drop(exit);
+                    res.is_ok()
+                })
+            }).collect();
+
+            let won = handles.into_iter().map(|handle| handle.join().unwrap()).filter(|&won| won).count();
+            assert_eq!(won, 1);
+            assert_eq!(exit.wait(), Ok(fired.load(Ordering::SeqCst)));
+        }
+    }
+
+    #[test]
+    fn fire_reports_listeners() {
+        let (signal, exit_a) = signal();
+        let exit_b = exit_a.clone();
+        let exit_c = signal.make_exit();
+        drop(exit_c);
+
+        assert_eq!(signal.fire(), Ok(2));
+        assert!(exit_a.join(exit_b).wait().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn placeholder_exits() {
+        let mut never = Exit::never();
+        let fired = Exit::fired();
+        assert!(never.is_live() && Exit::never().is_live());
+        assert!(!fired.is_live());
+
+        assert!(fired.wait().is_ok());
+        future::lazy(move || {
+            assert!(never.poll().unwrap().is_not_ready());
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn dummy_signal() {
+        let dummy = Signal::<()>::dummy();
+        assert_eq!(dummy.fire(), Err(FireError::NoListeners));
+
+        let dummy = Signal::<()>::dummy();
+        let exit = dummy.make_exit();
+        drop(dummy.clone());
+        drop(dummy);
+        assert!(exit.is_live());
+    }
+
+    #[test]
+    fn counts_listeners() {
+        let (signal, mut exit) = ::signal();
+        let other = exit.clone();
+        assert_eq!(signal.listener_count(), 2);
+        assert_eq!(exit.waiting_count(), 0);
+
+        future::lazy(move || {
+            assert!(exit.poll().unwrap().is_not_ready());
+            assert_eq!(exit.waiting_count(), 1);
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+
+        assert_eq!(other.listener_count(), 1);
+        assert_eq!(other.waiting_count(), 0);
+    }
+
+    #[test]
+    fn debug_shows_state() {
+        let (signal, exit) = signal();
+        let _other = exit.clone();
+        assert_eq!(format!("{:?}", exit), "Exit { fired: false, listeners: 2 }");
+
+        signal.fire().unwrap();
+        assert_eq!(
+            format!("{:?}", signal),
+            "Signal { fired: true, listeners: 2, armed: true, on_drop: FireOnLastDrop }",
+        );
+
+        let weak = signal.downgrade();
+        assert_eq!(format!("{:?}", weak), "WeakSignal { alive: true, fired: Some(true) }");
+        drop((signal, exit, _other));
+        assert_eq!(format!("{:?}", weak), "WeakSignal { alive: false, fired: None }");
+    }
+
+    #[test]
+    fn defuse_does_not_fire() {
+        let (signal, mut exit) = signal();
+        signal.defuse();
+
+        assert!(exit.is_live());
+        future::lazy(move || {
+            assert!(exit.poll().unwrap().is_not_ready());
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn signal_clones_fire_on_last_drop() {
+        let (signal_a, exit) = signal();
+        let signal_b = signal_a.clone();
+        let signal_c = signal_b.clone();
+
+        drop(signal_a);
+        assert!(exit.is_live());
+        signal_b.defuse();
+        assert!(exit.is_live());
+        drop(signal_c);
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    fn signal_clones_fire_on_drop() {
+        let (signal_a, exit) = signal();
+        let signal_a = signal_a.with_drop_behavior(DropBehavior::FireOnDrop);
+        let signal_b = signal_a.clone();
+
+        drop(signal_b);
+        assert!(!exit.is_live());
+        assert!(signal_a.is_fired());
+    }
+
+    #[test]
+    fn weak_signal() {
+        let (signal, exit) = signal();
+        let weak = signal.downgrade();
+        drop(weak.clone());
+        assert!(exit.is_live());
+
+        assert_eq!(weak.fire(), Ok(1));
+        assert!(signal.is_fired());
+        assert!(exit.wait().is_ok());
+
+        let weak = signal.downgrade();
+        drop(signal);
+        assert_eq!(weak.fire(), Err(FireError::NoListeners));
+    }
+
+    #[test]
+    fn weak_signal_does_not_keep_alive() {
+        let (signal, exit) = signal();
+        let _weak = signal.downgrade();
+
+        drop(signal);
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    fn exit_resolves_to_reason() {
+        let (signal, exit) = signal_with::<&'static str>();
+        let work = exit.clone().until(future::empty::<(), ()>());
+
+        assert_eq!(signal.fire_with("reload"), Ok(2));
+        assert_eq!(signal.fire_with("again"), Err(FireError::AlreadyFired));
+        assert_eq!(exit.wait(), Ok("reload"));
+        assert_eq!(work.wait(), Ok(None));
+
+        let (signal, exit) = signal_with::<u32>();
+        drop(signal);
+        assert_eq!(exit.wait(), Ok(0));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn wait_timeout() {
+        let (signal, exit) = signal();
+        assert!(!exit.wait_timeout(Duration::from_millis(20)));
+
+        ::std::thread::spawn(move || {
+            ::std::thread::sleep(Duration::from_millis(20));
+            signal.fire().unwrap();
+        });
+
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+        assert!(exit.wait_timeout(Duration::from_millis(0)));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn sleep_wakes_on_fire() {
+        let (signal, exit) = signal();
+        assert!(exit.sleep(Duration::from_millis(20)));
+
+        ::std::thread::spawn(move || {
+            ::std::thread::sleep(Duration::from_millis(20));
+            signal.fire().unwrap();
+        });
+
+        let start = Instant::now();
+        assert!(!exit.sleep(Duration::from_secs(3600)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn wait_deadline() {
+        let (signal, exit_a) = signal();
+        let exit_b = exit_a.clone();
+        let deadline = Instant::now() + Duration::from_millis(20);
+
+        let handle = ::std::thread::spawn(move || exit_b.wait_deadline(deadline));
+        assert!(!exit_a.wait_deadline(deadline));
+        assert!(!handle.join().unwrap());
+        assert!(Instant::now() >= deadline);
+
+        signal.fire().unwrap();
+        assert!(exit_a.wait_deadline(deadline));
+    }
+
+    #[test]
+    fn exit_is_fused() {
+        let (signal, mut exit) = signal();
+
+        future::lazy(move || {
+            assert!(exit.poll().unwrap().is_not_ready());
+            signal.fire().unwrap();
+
+            for _ in 0..3 {
+                assert!(exit.poll().unwrap().is_ready());
+            }
+
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn dropped_exits_deregister() {
+        let (_signal, exit) = signal();
+
+        future::lazy(move || {
+            for _ in 0..10 {
+                let mut exit = exit.clone();
+                assert!(exit.poll().unwrap().is_not_ready());
+            }
+
+            assert!(exit.shared.waiting.lock().tasks.is_empty());
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn fired_exits_do_not_register() {
+        let (signal, exit) = signal();
+        signal.fire().unwrap();
+
+        future::lazy(move || {
+            let mut exit2 = exit.clone();
+            assert!(exit2.poll().unwrap().is_ready());
+            assert!(exit2.inner.is_none());
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn clone_shares_state() {
+        let (_signal, exit) = signal();
+        let exit2 = exit.clone();
+
+        assert!(Arc::ptr_eq(&exit.shared, &exit2.shared));
+        assert!(exit2.inner.is_none());
+        assert!(exit.shared.waiting.lock().tasks.is_empty());
+    }
+
+    #[test]
+    fn reset_rearms_exits() {
+        let (signal, mut exit) = signal_with::<u32>();
+        assert!(!signal.reset());
+
+        signal.fire_with(1).unwrap();
+        assert!(!exit.is_live());
+        assert!(signal.reset());
+        assert!(exit.is_live());
+        assert!(!signal.is_fired());
+
+        let handle = ::std::thread::spawn(move || {
+            future::lazy(move || {
+                // the fire before the reset is observed once.
+                assert_eq!(exit.poll(), Ok(Async::Ready(1)));
+                assert!(exit.poll().unwrap().is_not_ready());
+                future::ok::<_, ()>(exit)
+            }).wait().unwrap()
+        });
+        let exit = handle.join().unwrap();
+
+        let waiter = exit.clone();
+        let handle = ::std::thread::spawn(move || waiter.wait());
+        ::std::thread::sleep(Duration::from_millis(20));
+        signal.fire_with(2).unwrap();
+
+        assert_eq!(handle.join().unwrap(), Ok(2));
+        assert_eq!(exit.wait(), Ok(2));
+    }
+
+    #[test]
+    fn reset_wakes_polled_exits() {
+        let (signal, _exit) = signal();
+        signal.fire().unwrap();
+        signal.reset();
+        let exit = signal.make_exit();
+
+        let handle = ::std::thread::spawn(move || {
+            // resolves through the future impl rather than the blocking wait.
+            let exit = exit.map(|()| 5);
+            Future::wait(exit)
+        });
+        ::std::thread::sleep(Duration::from_millis(20));
+        signal.fire().unwrap();
+
+        assert_eq!(handle.join().unwrap(), Ok(5));
+    }
+
+    #[test]
+    fn reset_before_poll() {
+        let (signal, mut exit) = signal_with::<u32>();
+
+        future::lazy(move || {
+            assert!(exit.poll().unwrap().is_not_ready());
+            signal.fire_with(3).unwrap();
+            assert!(signal.reset());
+
+            // notified but not polled before the reset, so it still resolves
+            // once, while its clones start out in the new generation.
+            let mut clone = exit.clone();
+            assert!(exit.is_live());
+            assert_eq!(exit.poll(), Ok(Async::Ready(3)));
+            assert!(exit.poll().unwrap().is_not_ready());
+            assert!(clone.poll().unwrap().is_not_ready());
+
+            signal.fire_with(4).unwrap();
+            assert_eq!(exit.poll(), Ok(Async::Ready(4)));
+            assert_eq!(clone.poll(), Ok(Async::Ready(4)));
+            assert_eq!(exit.poll(), Ok(Async::Ready(4)));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn reset_before_wait() {
+        let (signal, exit) = signal();
+        signal.fire().unwrap();
+        signal.reset();
+
+        assert!(exit.wait_timeout(Duration::from_millis(10)));
+        assert!(!exit.clone().wait_timeout(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn child_signals() {
+        let (parent, parent_exit) = signal_with::<u32>();
+        let child_a = parent.child();
+        let child_b = parent.child();
+        let (exit_a, exit_b) = (child_a.make_exit(), child_b.make_exit());
+
+        child_a.fire_with(1).unwrap();
+        assert!(!exit_a.is_live());
+        assert!(exit_b.is_live() && parent_exit.is_live());
+
+        let grandchild = child_b.child();
+        let exit_c = grandchild.make_exit();
+        parent.fire_with(2).unwrap();
+        assert_eq!(exit_a.wait(), Ok(1));
+        assert_eq!(exit_b.wait(), Ok(2));
+        assert_eq!(exit_c.wait(), Ok(2));
+        assert_eq!(parent_exit.wait(), Ok(2));
+
+        let late = parent.child();
+        assert!(late.is_fired());
+        assert_eq!(late.make_exit().wait(), Ok(2));
+    }
+
+    #[test]
+    fn dropped_children_are_pruned() {
+        let (parent, _exit) = signal();
+        let child = parent.child();
+        let grandchild = child.child();
+        assert_eq!(parent.shared.waiting.lock().hooks.len(), 1);
+
+        // the child fires its own children on its way out.
+        drop(child);
+        assert!(grandchild.is_fired());
+        assert!(parent.shared.waiting.lock().hooks.is_empty());
+
+        let child = parent.child();
+        let hook = Shared::hook(&parent.shared, Box::new(|_| true));
+        assert_eq!(parent.shared.waiting.lock().hooks.len(), 2);
+        drop(hook);
+        drop(child);
+        assert!(parent.shared.waiting.lock().hooks.is_empty());
+    }
+
+    #[test]
+    fn child_dropped_while_parent_fires() {
+        let (parent, _exit) = signal();
+        let child = parent.child();
+        let child_exit = child.make_exit();
+        let slot = Arc::new(Mutex::new(Some(child)));
+        let taken = slot.clone();
+        // drops the last handle to the child, which unregisters it from the
+        // parent while the parent's lock is held.
+        parent.shared.add_hook(Box::new(move |_| {
+            drop(taken.lock().take());
+            false
+        }));
+        drop(child_exit);
+
+        parent.fire().unwrap();
+        assert!(slot.lock().is_none());
+        assert!(parent.shared.waiting.lock().hooks.is_empty());
+    }
+
+    #[test]
+    fn any_exit() {
+        let (signal_a, exit_a) = signal_with::<u32>();
+        let (_signal_b, exit_b) = signal_with::<u32>();
+        let any = Exit::any(vec![exit_a, exit_b]);
+
+        signal_a.fire_with(1).unwrap();
+        assert_eq!(any.wait(), Ok(1));
+
+        let mut none = Exit::<u32>::any(None);
+        future::lazy(move || {
+            assert!(none.poll().unwrap().is_not_ready());
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn all_exits() {
+        let (signal_a, exit_a) = signal_with::<u32>();
+        let (signal_b, exit_b) = signal_with::<u32>();
+        let mut all = Exit::all(vec![exit_a, exit_b]);
+
+        future::lazy(move || {
+            signal_b.fire_with(2).unwrap();
+            assert!(all.poll().unwrap().is_not_ready());
+            signal_a.fire_with(1).unwrap();
+            assert_eq!(all.poll(), Ok(Async::Ready(vec![1, 2])));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+
+        assert_eq!(Exit::<u32>::all(None).wait(), Ok(vec![]));
+    }
+
+    #[test]
+    fn fire_and_wait_for_acks() {
+        let (signal, exit_a) = signal();
+        let exit_b = exit_a.clone();
+
+        let handle = ::std::thread::spawn(move || {
+            exit_a.clone().wait().unwrap();
+            exit_a.ack();
+            ::std::thread::sleep(Duration::from_millis(20));
+            drop(exit_b);
+        });
+
+        signal.fire_and_wait().wait().unwrap();
+        assert!(signal.shared.is_idle());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn fire_async_from_a_task() {
+        let (signal, exit) = signal();
+        let other = exit.clone();
+
+        let handle = ::std::thread::spawn(move || {
+            exit.clone().wait().unwrap();
+            exit.ack();
+            ::std::thread::sleep(Duration::from_millis(20));
+            drop(other);
+        });
+
+        let fire = future::lazy(|| signal.fire_async());
+        assert_eq!(fire.wait(), Ok(2));
+        assert!(signal.shared.is_idle());
+        assert_eq!(signal.fire_async().wait(), Err(FireError::AlreadyFired));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn fire_and_wait_timeout() {
+        let (signal, exit) = signal();
+        assert!(!signal.fire_and_wait_timeout(Duration::from_millis(20)));
+        assert!(signal.is_fired());
+
+        exit.ack();
+        assert!(signal.fire_and_wait_timeout(Duration::from_millis(20)));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn wait_idle() {
+        let (signal, exit) = signal();
+        signal.fire().unwrap();
+        assert!(!signal.wait_idle_timeout(Duration::from_millis(10)));
+
+        let handle = ::std::thread::spawn(move || {
+            ::std::thread::sleep(Duration::from_millis(20));
+            drop(exit);
+        });
+
+        signal.wait_idle().wait().unwrap();
+        assert!(signal.wait_idle_timeout(Duration::from_millis(0)));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn clone_works() {
         let (_signal, mut exit) = signal();
 
         future::lazy(move || {
@@ -279,8 +3197,71 @@ mod tests {
             assert!(exit2.inner.is_none());
             exit2.poll().unwrap();
 
-            assert!(exit.inner.unwrap().shared_id != exit2.inner.unwrap().shared_id);
+            assert!(exit.inner.as_ref().unwrap().shared_id != exit2.inner.as_ref().unwrap().shared_id);
             future::ok::<(), ()>(())
         }).wait().unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn traces_fires_and_exits() {
+        use std::fmt::Write;
+        use tracing::field::{Field, Visit};
+        use tracing::{span, Event, Metadata, Subscriber};
+
+        struct Events(Arc<Mutex<Vec<String>>>);
+
+        struct Line<'a>(&'a mut String);
+
+        impl<'a> Visit for Line<'a> {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                let _ = write!(self.0, "{}={:?} ", field.name(), value);
+            }
+        }
+
+        impl Subscriber for Events {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &span::Attributes) -> span::Id {
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+
+            fn event(&self, event: &Event) {
+                let mut line = String::new();
+                event.record(&mut Line(&mut line));
+                self.0.lock().push(line);
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        ::tracing::subscriber::with_default(Events(events.clone()), || {
+            let (signal, exit) = signal();
+            let signal = signal.with_name("db");
+            let mut polled = exit.clone();
+
+            future::lazy(|| {
+                assert_eq!(polled.poll(), Ok(Async::NotReady));
+                signal.fire().unwrap();
+                assert_eq!(polled.poll(), Ok(Async::Ready(())));
+                future::ok::<(), ()>(())
+            }).wait().unwrap();
+
+            assert_eq!(::futures::Future::wait(exit), Ok(()));
+            drop(polled);
+        });
+
+        let events = events.lock();
+        assert_eq!(events.len(), 4, "{:?}", *events);
+        assert!(events[0].contains("exit signal fired") && events[0].contains("signal=\"db\" listeners=2"));
+        assert!(events[1].contains("exit resolved") && events[1].contains("pending="));
+        assert!(events[2].contains("exit resolved") && !events[2].contains("pending="));
+        assert!(events[3].contains("exits drained"));
+    }
 }
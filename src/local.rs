@@ -0,0 +1,564 @@
+//! Single-threaded counterparts of `Signal` and `Exit`, for executors that
+//! never move tasks between threads, such as on wasm. They share state
+//! through `Rc` and `Cell` instead of atomics and locks.
+
+use futures::prelude::*;
+use futures::task::{self, Task};
+
+use std::boxed::Box;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use {DropBehavior, ExitHandle, FireError, SignalHandle};
+
+// run on every fire with the reason. returns whether to keep it registered.
+type LocalHook<T> = Box<dyn FnMut(Option<&T>) -> bool>;
+
+struct Inner<T> {
+    // the reason of the latest fire, kept across a reset for exits yet to
+    // observe it. `None` when fired by drop.
+    reason: RefCell<Option<T>>,
+    fired: Cell<bool>,
+    // bumped by each reset.
+    generation: Cell<usize>,
+    tasks: RefCell<BTreeMap<usize, Task>>,
+    hooks: RefCell<BTreeMap<usize, LocalHook<T>>>,
+    next_id: Cell<usize>,
+    listeners: Cell<usize>,
+    // number of armed `LocalSignal` handles.
+    signals: Cell<usize>,
+    // the hook firing this signal along with its parent, removed on drop.
+    parent: Cell<Option<(Weak<Inner<T>>, usize)>>,
+}
+
+impl<T> Inner<T> {
+    fn new() -> Inner<T> {
+        Inner {
+            reason: RefCell::new(None),
+            fired: Cell::new(false),
+            generation: Cell::new(0),
+            tasks: RefCell::new(BTreeMap::new()),
+            hooks: RefCell::new(BTreeMap::new()),
+            next_id: Cell::new(0),
+            listeners: Cell::new(0),
+            signals: Cell::new(1),
+            parent: Cell::new(None),
+        }
+    }
+
+    fn is_live(&self) -> bool {
+        !self.fired.get()
+    }
+
+    // the number of fires so far, counting the current one.
+    fn fires(&self) -> usize {
+        self.generation.get() + self.fired.get() as usize
+    }
+
+    fn next_id(&self) -> usize {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+
+    fn set(&self, reason: Option<T>) -> Result<usize, FireError> {
+        if self.fired.get() {
+            return Err(FireError::AlreadyFired)
+        }
+
+        self.fired.set(true);
+        *self.reason.borrow_mut() = reason;
+        // take the tasks and hooks first, so a woken task or a hook
+        // registering again can't find them borrowed.
+        let tasks = ::std::mem::take(&mut *self.tasks.borrow_mut());
+        for task in tasks.values() {
+            task.notify();
+        }
+
+        let mut hooks = ::std::mem::take(&mut *self.hooks.borrow_mut());
+        {
+            let reason = self.reason.borrow();
+            hooks.retain(|_, hook| hook(reason.as_ref()));
+        }
+        self.hooks.borrow_mut().append(&mut hooks);
+
+        match self.listeners.get() {
+            0 => Err(FireError::NoListeners),
+            n => Ok(n),
+        }
+    }
+
+    fn reset(&self) -> bool {
+        if !self.fired.get() {
+            return false
+        }
+
+        self.fired.set(false);
+        self.generation.set(self.generation.get() + 1);
+        true
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        if let Some((parent, id)) = self.parent.take() {
+            if let Some(parent) = parent.upgrade() {
+                let _ = parent.hooks.borrow_mut().remove(&id);
+            }
+        }
+    }
+}
+
+/// Single-threaded counterpart of `Signal`, which isn't `Send`. Created by
+/// `local_signal`.
+///
+/// Like `Signal`, it fires when its last armed handle is dropped, and clones
+/// inherit the drop behavior of the handle they were cloned from.
+pub struct LocalSignal<T = ()> {
+    inner: Rc<Inner<T>>,
+    // whether to fire on drop.
+    armed: bool,
+    on_drop: DropBehavior,
+}
+
+impl<T> LocalSignal<T> {
+    fn new() -> LocalSignal<T> {
+        LocalSignal { inner: Rc::new(Inner::new()), armed: true, on_drop: DropBehavior::default() }
+    }
+
+    /// Create a signal that isn't connected to anything. See `Signal::dummy`.
+    pub fn dummy() -> LocalSignal<T> {
+        // never disarmed, so the count of armed handles never drops to zero.
+        LocalSignal { inner: Rc::new(Inner::new()), armed: false, on_drop: DropBehavior::default() }
+    }
+
+    /// Fire the signal with the given reason. See `Signal::fire_with`.
+    pub fn fire_with(&self, reason: T) -> Result<usize, FireError> {
+        self.inner.set(Some(reason))
+    }
+
+    /// Fire the signal like `fire_with`, which never waits on a lock here
+    /// either. See `Signal::try_fire_with`.
+    pub fn try_fire_with(&self, reason: T) -> Result<usize, FireError> {
+        self.fire_with(reason)
+    }
+
+    /// Get an exit future.
+    pub fn make_exit(&self) -> LocalExit<T> {
+        LocalExit::new(self.inner.clone())
+    }
+
+    /// Whether the signal has already been fired.
+    pub fn is_fired(&self) -> bool {
+        !self.inner.is_live()
+    }
+
+    /// The number of live exits for this signal.
+    pub fn listener_count(&self) -> usize {
+        self.inner.listeners.get()
+    }
+
+    /// Re-arm a fired signal, returning whether it had been fired. See
+    /// `Signal::reset`.
+    pub fn reset(&self) -> bool {
+        self.inner.reset()
+    }
+
+    /// Create a child signal, which fires along with this one but can also
+    /// be fired on its own. See `Signal::child`.
+    pub fn child(&self) -> LocalSignal<T> where T: Clone + 'static {
+        let child = LocalSignal::new();
+        let weak = Rc::downgrade(&child.inner);
+        let mut hook: LocalHook<T> = Box::new(move |reason| match weak.upgrade() {
+            Some(child) => {
+                let _ = child.set(reason.cloned());
+                true
+            }
+            None => false,
+        });
+
+        if !self.is_fired() || hook(self.inner.reason.borrow().as_ref()) {
+            let id = self.inner.next_id();
+            let _ = self.inner.hooks.borrow_mut().insert(id, hook);
+            child.inner.parent.set(Some((Rc::downgrade(&self.inner), id)));
+        }
+
+        child
+    }
+
+    /// Consume the signal without firing it. See `Signal::defuse`.
+    pub fn defuse(mut self) {
+        self.disarm();
+    }
+
+    /// Set what dropping this handle does. Defaults to
+    /// `DropBehavior::FireOnLastDrop`.
+    pub fn with_drop_behavior(mut self, on_drop: DropBehavior) -> LocalSignal<T> {
+        self.on_drop = on_drop;
+        self
+    }
+
+    // returns whether this was the last armed handle.
+    fn disarm(&mut self) -> bool {
+        self.armed = false;
+        let signals = self.inner.signals.get() - 1;
+        self.inner.signals.set(signals);
+        signals == 0
+    }
+
+    /// Get a weak handle which can fire the signal. See `Signal::downgrade`.
+    pub fn downgrade(&self) -> LocalWeakSignal<T> {
+        LocalWeakSignal { inner: Rc::downgrade(&self.inner) }
+    }
+}
+
+impl<T: Default> LocalSignal<T> {
+    /// Fire the signal with the default reason. See `Signal::fire`.
+    pub fn fire(&self) -> Result<usize, FireError> {
+        self.fire_with(T::default())
+    }
+
+    /// Fire the signal with the default reason. See `Signal::try_fire`.
+    pub fn try_fire(&self) -> Result<usize, FireError> {
+        self.try_fire_with(T::default())
+    }
+}
+
+impl<T> Clone for LocalSignal<T> {
+    fn clone(&self) -> LocalSignal<T> {
+        self.inner.signals.set(self.inner.signals.get() + 1);
+        LocalSignal { inner: self.inner.clone(), armed: true, on_drop: self.on_drop }
+    }
+}
+
+impl<T> Drop for LocalSignal<T> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return
+        }
+
+        let last = self.disarm();
+        if last || self.on_drop == DropBehavior::FireOnDrop {
+            let _ = self.inner.set(None);
+        }
+    }
+}
+
+impl<T> fmt::Debug for LocalSignal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalSignal")
+            .field("fired", &self.is_fired())
+            .field("listeners", &self.listener_count())
+            .field("armed", &self.armed)
+            .field("on_drop", &self.on_drop)
+            .finish()
+    }
+}
+
+impl<T: Clone + Default + 'static> SignalHandle for LocalSignal<T> {
+    type Reason = T;
+    type Exit = LocalExit<T>;
+
+    fn fire_with(&self, reason: T) -> Result<usize, FireError> {
+        LocalSignal::fire_with(self, reason)
+    }
+
+    fn make_exit(&self) -> LocalExit<T> {
+        LocalSignal::make_exit(self)
+    }
+
+    fn is_fired(&self) -> bool {
+        LocalSignal::is_fired(self)
+    }
+
+    fn listener_count(&self) -> usize {
+        LocalSignal::listener_count(self)
+    }
+
+    fn reset(&self) -> bool {
+        LocalSignal::reset(self)
+    }
+
+    fn child(&self) -> LocalSignal<T> {
+        LocalSignal::child(self)
+    }
+
+    fn defuse(self) {
+        LocalSignal::defuse(self)
+    }
+
+    fn with_drop_behavior(self, on_drop: DropBehavior) -> LocalSignal<T> {
+        LocalSignal::with_drop_behavior(self, on_drop)
+    }
+}
+
+/// Weak handle to a `LocalSignal`, obtained through `LocalSignal::downgrade`.
+pub struct LocalWeakSignal<T = ()> {
+    inner: Weak<Inner<T>>,
+}
+
+impl<T> LocalWeakSignal<T> {
+    /// Fire the signal. See `WeakSignal::fire_with`.
+    pub fn fire_with(&self, reason: T) -> Result<usize, FireError> {
+        match self.inner.upgrade() {
+            Some(inner) => inner.set(Some(reason)),
+            None => Err(FireError::NoListeners),
+        }
+    }
+}
+
+impl<T: Default> LocalWeakSignal<T> {
+    /// Fire the signal with the default reason. See `WeakSignal::fire`.
+    pub fn fire(&self) -> Result<usize, FireError> {
+        self.fire_with(T::default())
+    }
+}
+
+impl<T> Clone for LocalWeakSignal<T> {
+    fn clone(&self) -> LocalWeakSignal<T> {
+        LocalWeakSignal { inner: self.inner.clone() }
+    }
+}
+
+impl<T> fmt::Debug for LocalWeakSignal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.inner.upgrade();
+        f.debug_struct("LocalWeakSignal")
+            .field("alive", &inner.is_some())
+            .field("fired", &inner.map(|inner| !inner.is_live()))
+            .finish()
+    }
+}
+
+/// Single-threaded counterpart of `Exit`, which isn't `Send`: a future
+/// resolving with the reason once its `LocalSignal` fires.
+pub struct LocalExit<T = ()> {
+    inner: Rc<Inner<T>>,
+    // registered on first poll.
+    id: Option<usize>,
+    // the number of fires this exit has observed. see `Exit`.
+    seen: usize,
+}
+
+impl<T> LocalExit<T> {
+    fn new(inner: Rc<Inner<T>>) -> LocalExit<T> {
+        inner.listeners.set(inner.listeners.get() + 1);
+        let seen = inner.generation.get();
+        LocalExit { inner, id: None, seen }
+    }
+
+    /// Check if the signal is live without scheduling a wakeup.
+    pub fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+
+    /// The number of live exits for this signal, including this one.
+    pub fn listener_count(&self) -> usize {
+        self.inner.listeners.get()
+    }
+
+    /// Acknowledge that the signal has been handled, by dropping this exit.
+    pub fn ack(self) {}
+
+    // whether there's a fire this exit hasn't observed, or it observed the
+    // current one and the signal is still fired. marks it observed.
+    fn observe(&mut self) -> bool {
+        let fires = self.inner.fires();
+        if fires > self.seen {
+            self.seen = fires;
+            return true
+        }
+
+        !self.inner.is_live()
+    }
+}
+
+impl<T: Clone + Default> Future for LocalExit<T> {
+    type Item = T;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<T, ()> {
+        if self.observe() {
+            return Ok(Async::Ready(self.inner.reason.borrow().clone().unwrap_or_default()))
+        }
+
+        let inner = &self.inner;
+        let id = *self.id.get_or_insert_with(|| inner.next_id());
+        let _ = inner.tasks.borrow_mut().insert(id, task::current());
+
+        Ok(Async::NotReady)
+    }
+}
+
+impl<T: Clone + Default> ExitHandle for LocalExit<T> {
+    fn is_live(&self) -> bool {
+        LocalExit::is_live(self)
+    }
+
+    fn listener_count(&self) -> usize {
+        LocalExit::listener_count(self)
+    }
+}
+
+impl<T> Clone for LocalExit<T> {
+    fn clone(&self) -> LocalExit<T> {
+        LocalExit::new(self.inner.clone())
+    }
+}
+
+impl<T> Drop for LocalExit<T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.inner.tasks.borrow_mut().remove(&id);
+        }
+        self.inner.listeners.set(self.inner.listeners.get() - 1);
+    }
+}
+
+impl<T> fmt::Debug for LocalExit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LocalExit")
+            .field("fired", &!self.is_live())
+            .field("listeners", &self.listener_count())
+            .finish()
+    }
+}
+
+/// Create a single-threaded signal and exit pair. See `signal`.
+pub fn local_signal() -> (LocalSignal, LocalExit) {
+    local_signal_with()
+}
+
+/// Create a single-threaded signal and exit pair whose exits resolve with
+/// the reason the signal was fired with. See `signal_with`.
+pub fn local_signal_with<T>() -> (LocalSignal<T>, LocalExit<T>) {
+    let signal = LocalSignal::new();
+    let exit = signal.make_exit();
+
+    (signal, exit)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use super::*;
+
+    #[test]
+    fn fires_exits() {
+        let (signal, exit) = local_signal_with::<u32>();
+        let mut clone = exit.clone();
+        assert_eq!(signal.listener_count(), 2);
+
+        future::lazy(move || {
+            assert_eq!(clone.poll(), Ok(Async::NotReady));
+            assert_eq!(signal.fire_with(3), Ok(2));
+            assert_eq!(clone.poll(), Ok(Async::Ready(3)));
+            assert_eq!(signal.fire_with(4), Err(FireError::AlreadyFired));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+
+        assert_eq!(exit.wait(), Ok(3));
+    }
+
+    #[test]
+    fn fires_on_last_drop() {
+        let (signal, exit) = local_signal();
+        let clone = signal.clone();
+        drop(signal);
+        assert!(exit.is_live());
+
+        drop(clone);
+        assert_eq!(exit.wait(), Ok(()));
+    }
+
+    #[test]
+    fn drop_behavior() {
+        let (signal, exit) = local_signal();
+        let clone = signal.clone();
+        signal.defuse();
+        drop(clone.with_drop_behavior(DropBehavior::FireOnDrop).clone());
+        assert!(!exit.is_live());
+
+        let (signal, exit) = local_signal();
+        drop(LocalSignal::<()>::dummy());
+        signal.defuse();
+        assert!(exit.is_live());
+    }
+
+    #[test]
+    fn resets() {
+        let (signal, mut exit) = local_signal_with::<u32>();
+        let mut late = exit.clone();
+
+        future::lazy(move || {
+            signal.fire_with(1).unwrap();
+            assert_eq!(exit.poll(), Ok(Async::Ready(1)));
+            assert!(signal.reset());
+            assert!(!signal.reset());
+
+            assert_eq!(exit.poll(), Ok(Async::NotReady));
+            // observes the fire from before the reset once.
+            assert_eq!(late.poll(), Ok(Async::Ready(1)));
+            assert_eq!(late.poll(), Ok(Async::NotReady));
+
+            signal.fire_with(2).unwrap();
+            assert_eq!(exit.poll(), Ok(Async::Ready(2)));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn children() {
+        let (parent, exit) = local_signal_with::<u32>();
+        let child = parent.child();
+        let sibling = parent.child();
+        let child_exit = child.make_exit();
+        let sibling_exit = sibling.make_exit();
+
+        sibling.fire_with(1).unwrap();
+        assert!(exit.is_live());
+        assert!(child_exit.is_live());
+        assert_eq!(sibling_exit.wait(), Ok(1));
+
+        // forgotten once every handle to it is gone.
+        drop(sibling);
+        assert_eq!(parent.inner.hooks.borrow().len(), 1);
+
+        parent.fire_with(2).unwrap();
+        assert_eq!(child_exit.wait(), Ok(2));
+        assert_eq!(parent.child().make_exit().wait(), Ok(2));
+        assert_eq!(exit.wait(), Ok(2));
+    }
+
+    #[test]
+    fn weak_handles() {
+        let (signal, exit) = local_signal();
+        let weak = signal.downgrade();
+        signal.defuse();
+        assert!(exit.is_live());
+
+        assert_eq!(weak.fire(), Ok(1));
+        drop(exit);
+        assert_eq!(weak.fire(), Err(FireError::NoListeners));
+    }
+
+    // fires through the traits, for either kind of signal.
+    fn fire_generic<S: SignalHandle<Reason = u32>>(signal: S) -> u32 {
+        let exit = signal.make_exit();
+        let child = signal.child();
+        assert!(exit.is_live());
+        assert_eq!(child.listener_count(), 0);
+
+        signal.fire_with(7).unwrap();
+        assert!(child.is_fired());
+        exit.wait().unwrap()
+    }
+
+    #[test]
+    fn generic_over_signals() {
+        assert_eq!(fire_generic(local_signal_with::<u32>().0), 7);
+        assert_eq!(fire_generic(::signal_with::<u32>().0), 7);
+    }
+}
@@ -0,0 +1,71 @@
+//! The mutex used for shared state: parking_lot's with `std`, otherwise a
+//! spin lock.
+
+#[cfg(feature = "std")]
+pub(crate) use parking_lot::{Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use self::spin::{Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+mod spin {
+    use std::cell::UnsafeCell;
+    use std::ops::{Deref, DerefMut};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    // the lock hands out access to the value to one thread at a time.
+    unsafe impl<T: Send> Send for Mutex<T> {}
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(value: T) -> Mutex<T> {
+            Mutex { locked: AtomicBool::new(false), value: UnsafeCell::new(value) }
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+                while self.locked.load(Ordering::Relaxed) {
+                    ::std::hint::spin_loop();
+                }
+            }
+
+            MutexGuard { mutex: self }
+        }
+
+        pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            match self.locked.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => Some(MutexGuard { mutex: self }),
+                Err(_) => None,
+            }
+        }
+    }
+
+    pub(crate) struct MutexGuard<'a, T: 'a> {
+        mutex: &'a Mutex<T>,
+    }
+
+    impl<'a, T> Deref for MutexGuard<'a, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<'a, T> DerefMut for MutexGuard<'a, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<'a, T> Drop for MutexGuard<'a, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.store(false, Ordering::Release);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+/// Hooks observing a signal's lifecycle, installed with
+/// `Signal::with_metrics`.
+///
+/// Every method does nothing by default. They are called synchronously from
+/// whichever thread triggered the event, so they should be cheap.
+pub trait Metrics: Send + Sync {
+    /// An `Exit` was created, either from the signal or by cloning another.
+    fn listener_registered(&self) {}
+
+    /// An `Exit` was dropped.
+    fn listener_deregistered(&self) {}
+
+    /// The signal fired, taking `latency` to run its hooks and wake every
+    /// waiting task.
+    fn fired(&self, latency: Duration) {
+        let _ = latency;
+    }
+
+    /// The last `Exit` was dropped `duration` after the signal fired.
+    fn drained(&self, duration: Duration) {
+        let _ = duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use super::*;
+
+    #[derive(Default)]
+    struct Counts {
+        registered: AtomicUsize,
+        deregistered: AtomicUsize,
+        fired: AtomicUsize,
+        drained: AtomicUsize,
+    }
+
+    impl Metrics for Arc<Counts> {
+        fn listener_registered(&self) {
+            self.registered.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn listener_deregistered(&self) {
+            self.deregistered.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn fired(&self, _: Duration) {
+            self.fired.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn drained(&self, _: Duration) {
+            self.drained.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn reports_lifecycle() {
+        let counts = Arc::new(Counts::default());
+        let signal = ::signal_only().with_metrics(counts.clone());
+
+        let exit = signal.make_exit();
+        let other = exit.clone();
+        drop(other);
+        assert_eq!(counts.registered.load(Ordering::Relaxed), 2);
+        assert_eq!(counts.deregistered.load(Ordering::Relaxed), 1);
+
+        signal.fire().unwrap();
+        assert_eq!(counts.fired.load(Ordering::Relaxed), 1);
+        assert_eq!(counts.drained.load(Ordering::Relaxed), 0);
+
+        drop(exit);
+        assert_eq!(counts.deregistered.load(Ordering::Relaxed), 2);
+        assert_eq!(counts.drained.load(Ordering::Relaxed), 1);
+    }
+}
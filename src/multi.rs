@@ -0,0 +1,214 @@
+use futures::prelude::*;
+use futures::task::AtomicTask;
+
+use lock::Mutex;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use FireError;
+
+struct State {
+    // number of times fired.
+    events: u64,
+    closed: bool,
+    next_id: usize,
+    listeners: usize,
+    tasks: BTreeMap<usize, Arc<AtomicTask>>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn notify_all<F: FnOnce(&mut State)>(&self, update: F) -> usize {
+        let (listeners, wake_up) = {
+            let mut state = self.state.lock();
+            update(&mut state);
+            (state.listeners, state.tasks.values().cloned().collect::<Vec<_>>())
+        };
+
+        for task in wake_up {
+            task.notify();
+        }
+
+        listeners
+    }
+}
+
+/// Signal that can fire many times, delivering each event to every
+/// `ExitStream`. The streams end once it is dropped.
+pub struct MultiSignal {
+    shared: Arc<Shared>,
+}
+
+impl MultiSignal {
+    /// Deliver an event to every `ExitStream`.
+    ///
+    /// Returns the number of streams that were alive to receive it, or
+    /// `FireError::NoListeners` if there were none.
+    pub fn fire(&self) -> Result<usize, FireError> {
+        match self.shared.notify_all(|state| state.events += 1) {
+            0 => Err(FireError::NoListeners),
+            n => Ok(n),
+        }
+    }
+
+    /// Get a stream of the events fired from now on.
+    pub fn make_stream(&self) -> ExitStream {
+        let seen = self.shared.state.lock().events;
+        ExitStream::new(self.shared.clone(), seen)
+    }
+}
+
+impl Drop for MultiSignal {
+    fn drop(&mut self) {
+        self.shared.notify_all(|state| state.closed = true);
+    }
+}
+
+/// Stream yielding `()` for each time a `MultiSignal` fires, ending once the
+/// signal is dropped.
+///
+/// Clones start out with the same events pending as the original.
+pub struct ExitStream {
+    shared: Arc<Shared>,
+    seen: u64,
+    slot: Option<(usize, Arc<AtomicTask>)>,
+}
+
+impl ExitStream {
+    fn new(shared: Arc<Shared>, seen: u64) -> ExitStream {
+        shared.state.lock().listeners += 1;
+        ExitStream { shared, seen, slot: None }
+    }
+}
+
+impl Stream for ExitStream {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<()>, ()> {
+        let shared = &self.shared;
+        let &mut (_, ref task) = self.slot.get_or_insert_with(|| {
+            let task = Arc::new(AtomicTask::new());
+            let mut state = shared.state.lock();
+
+            let id = state.next_id;
+            state.next_id += 1;
+            state.tasks.insert(id, task.clone());
+
+            (id, task)
+        });
+
+        task.register();
+
+        let state = shared.state.lock();
+        if self.seen < state.events {
+            self.seen += 1;
+            Ok(Async::Ready(Some(())))
+        } else if state.closed {
+            Ok(Async::Ready(None))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+impl Clone for ExitStream {
+    fn clone(&self) -> ExitStream {
+        ExitStream::new(self.shared.clone(), self.seen)
+    }
+}
+
+impl Drop for ExitStream {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.listeners -= 1;
+
+        if let Some((id, _)) = self.slot {
+            state.tasks.remove(&id);
+        }
+    }
+}
+
+/// Create a multi-shot signal and a stream of its events.
+pub fn multi_signal() -> (MultiSignal, ExitStream) {
+    let signal = MultiSignal {
+        shared: Arc::new(Shared {
+            state: Mutex::new(State {
+                events: 0,
+                closed: false,
+                next_id: 0,
+                listeners: 0,
+                tasks: BTreeMap::new(),
+            }),
+        }),
+    };
+    let stream = signal.make_stream();
+
+    (signal, stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use super::*;
+
+    #[test]
+    fn delivers_each_event() {
+        let (signal, stream) = multi_signal();
+        let other = stream.clone();
+
+        assert_eq!(signal.fire(), Ok(2));
+        assert_eq!(signal.fire(), Ok(2));
+        drop(signal);
+
+        assert_eq!(stream.collect().wait(), Ok(vec![(), ()]));
+        assert_eq!(other.collect().wait(), Ok(vec![(), ()]));
+    }
+
+    #[test]
+    fn new_streams_only_see_later_events() {
+        let (signal, stream) = multi_signal();
+        signal.fire().unwrap();
+
+        let late = signal.make_stream();
+        signal.fire().unwrap();
+        drop(signal);
+
+        assert_eq!(stream.collect().wait().unwrap().len(), 2);
+        assert_eq!(late.collect().wait().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn wakes_waiting_streams() {
+        let (signal, stream) = multi_signal();
+
+        let handle = ::std::thread::spawn(move || stream.collect().wait());
+        ::std::thread::sleep(::std::time::Duration::from_millis(20));
+        signal.fire().unwrap();
+        drop(signal);
+
+        assert_eq!(handle.join().unwrap(), Ok(vec![()]));
+    }
+
+    #[test]
+    fn no_listeners() {
+        let (signal, stream) = multi_signal();
+        drop(stream);
+
+        assert_eq!(signal.fire(), Err(FireError::NoListeners));
+    }
+
+    #[test]
+    fn pending_until_fired() {
+        let (_signal, mut stream) = multi_signal();
+
+        future::lazy(move || {
+            assert_eq!(stream.poll(), Ok(Async::NotReady));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+}
@@ -0,0 +1,46 @@
+//! Integration with operating system signals.
+
+use std::io;
+
+use Exit;
+
+#[cfg(unix)]
+pub mod unix;
+#[cfg(windows)]
+pub mod windows;
+
+impl Exit {
+    /// Get an exit which fires when the process is interrupted with Ctrl-C.
+    ///
+    /// Installs a process-wide `SIGINT` handler the first time it's called.
+    /// The first Ctrl-C fires the exit instead of terminating the process;
+    /// unless something else is still bound to `SIGINT`, the handler is then
+    /// removed, so a second Ctrl-C terminates the process as usual.
+    #[cfg(unix)]
+    pub fn on_ctrl_c() -> io::Result<Exit> {
+        let (signal, exit) = ::signal();
+        unix::watch(::libc::SIGINT, move || {
+            let _ = signal.fire();
+            false
+        })?;
+
+        Ok(exit)
+    }
+
+    /// Get an exit which fires when the process is interrupted with Ctrl-C.
+    ///
+    /// Installs a process-wide console control handler the first time it's
+    /// called. The first Ctrl-C fires the exit instead of terminating the
+    /// process; unless something else is still bound to `CTRL_C_EVENT`, a
+    /// second Ctrl-C terminates the process as usual.
+    #[cfg(windows)]
+    pub fn on_ctrl_c() -> io::Result<Exit> {
+        let (signal, exit) = ::signal();
+        windows::watch(windows::CTRL_C_EVENT, move || {
+            let _ = signal.fire();
+            false
+        })?;
+
+        Ok(exit)
+    }
+}
@@ -0,0 +1,481 @@
+//! Binding POSIX signals to a `Signal`.
+//!
+//! Handlers are installed once per process and signal number, the first time
+//! it's bound. They chain to any handler that was installed before them, but
+//! not to the default action, so a bound signal no longer terminates the
+//! process. Once nothing is bound to a signal any more, noticed on its next
+//! delivery, the handler from before is put back.
+
+use libc::{self, c_int, c_void, siginfo_t};
+use parking_lot::Mutex;
+
+use std::io;
+use std::mem;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::io::RawFd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicUsize, Ordering};
+use std::thread;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use {Exit, FireHook, Shared, Signal};
+
+/// The signals that conventionally ask a process to shut down.
+pub const TERMINATION_SIGNALS: [c_int; 4] = [libc::SIGTERM, libc::SIGINT, libc::SIGHUP, libc::SIGQUIT];
+
+// messages down the pipe are a native-endian u32 key: a signal number up
+// to `MAX_SIGNAL`, or a `HandlerSignal` id above it.
+const MAX_SIGNAL: c_int = 64;
+const SLOTS: usize = MAX_SIGNAL as usize + 1;
+const KEY_SIZE: usize = 4;
+
+// run on the watcher thread for each delivery. returns whether to keep it.
+type Callback = Box<dyn FnMut() -> bool + Send>;
+
+// the write end of the self-pipe, for the signal handler.
+static PIPE: AtomicI32 = AtomicI32::new(-1);
+static INSTALLED: [AtomicBool; SLOTS] = [const { AtomicBool::new(false) }; SLOTS];
+// the handlers that were installed before ours, to chain to.
+static PREVIOUS: [AtomicUsize; SLOTS] = [const { AtomicUsize::new(0) }; SLOTS];
+static PREVIOUS_SIGINFO: [AtomicBool; SLOTS] = [const { AtomicBool::new(false) }; SLOTS];
+static WATCHER: OnceLock<io::Result<Watcher>> = OnceLock::new();
+static NEXT_HANDLER: AtomicU32 = AtomicU32::new(MAX_SIGNAL as u32 + 1);
+
+struct Watcher {
+    callbacks: Mutex<Vec<(u32, Callback)>>,
+    // the actions our handlers replaced, to put back. only changed with the
+    // callbacks locked.
+    replaced: Mutex<Vec<(c_int, libc::sigaction)>>,
+}
+
+impl Watcher {
+    fn start() -> io::Result<Watcher> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        for &fd in &fds {
+            set_cloexec(fd)?;
+        }
+        // the handler must never block on a full pipe.
+        set_nonblocking(fds[1])?;
+
+        let read = fds[0];
+        thread::Builder::new()
+            .name("exit-future-signals".into())
+            .spawn(move || watch_pipe(read))?;
+        PIPE.store(fds[1], Ordering::Release);
+
+        Ok(Watcher { callbacks: Mutex::new(Vec::new()), replaced: Mutex::new(Vec::new()) })
+    }
+
+    fn dispatch(&self, key: u32) {
+        let mut callbacks = self.callbacks.lock();
+        callbacks.retain_mut(|&mut (bound, ref mut callback)| bound != key || callback());
+
+        if key <= MAX_SIGNAL as u32 && !callbacks.iter().any(|&(bound, _)| bound == key) {
+            self.restore(key as c_int);
+        }
+    }
+
+    // put back the action our handler replaced, so the signal takes its
+    // default action again. must be called with the callbacks locked.
+    fn restore(&self, signo: c_int) {
+        let mut replaced = self.replaced.lock();
+        if let Some(i) = replaced.iter().position(|&(bound, _)| bound == signo) {
+            if unsafe { libc::sigaction(signo, &replaced[i].1, ::std::ptr::null_mut()) } == 0 {
+                replaced.swap_remove(i);
+                INSTALLED[signo as usize].store(false, Ordering::Release);
+            }
+        }
+    }
+}
+
+fn watcher() -> io::Result<&'static Watcher> {
+    match *WATCHER.get_or_init(Watcher::start) {
+        Ok(ref watcher) => Ok(watcher),
+        Err(ref err) => Err(io::Error::new(err.kind(), err.to_string())),
+    }
+}
+
+fn watch_pipe(read: RawFd) {
+    let mut buf = [0u8; KEY_SIZE * 16];
+    // bytes of a message split across reads.
+    let mut partial = 0;
+    loop {
+        let n = unsafe { libc::read(read, buf[partial..].as_mut_ptr() as *mut c_void, buf.len() - partial) };
+        if n < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+            continue
+        }
+        if n <= 0 {
+            return
+        }
+
+        let len = partial + n as usize;
+        if let Ok(watcher) = watcher() {
+            for key in buf[..len].chunks_exact(KEY_SIZE) {
+                watcher.dispatch(u32::from_ne_bytes([key[0], key[1], key[2], key[3]]));
+            }
+        }
+
+        partial = len % KEY_SIZE;
+        buf.copy_within(len - partial..len, 0);
+    }
+}
+
+// async-signal-safe. messages fit in a single atomic pipe write, and are
+// dropped if the pipe is full.
+fn send(key: u32) {
+    let fd = PIPE.load(Ordering::Acquire);
+    if fd >= 0 {
+        let bytes = key.to_ne_bytes();
+        unsafe {
+            libc::write(fd, bytes.as_ptr() as *const c_void, bytes.len());
+        }
+    }
+}
+
+fn set_cloexec(fd: RawFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error())
+    }
+
+    Ok(())
+}
+
+fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error())
+    }
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "emscripten"))]
+fn errno_location() -> Option<*mut c_int> {
+    Some(unsafe { libc::__errno_location() })
+}
+
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+fn errno_location() -> Option<*mut c_int> {
+    Some(unsafe { libc::__errno() })
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "dragonfly"))]
+fn errno_location() -> Option<*mut c_int> {
+    Some(unsafe { libc::__error() })
+}
+
+#[cfg(not(any(
+    target_os = "linux", target_os = "emscripten", target_os = "android", target_os = "netbsd",
+    target_os = "openbsd", target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "dragonfly",
+)))]
+fn errno_location() -> Option<*mut c_int> {
+    None
+}
+
+// only async-signal-safe calls are allowed here, and errno must be left as
+// it was found.
+extern "C" fn handle(signo: c_int, info: *mut siginfo_t, context: *mut c_void) {
+    let errno = errno_location();
+    let saved = errno.map(|errno| unsafe { *errno });
+
+    send(signo as u32);
+
+    let slot = signo as usize;
+    let previous = PREVIOUS[slot].load(Ordering::Acquire);
+    if previous != libc::SIG_DFL && previous != libc::SIG_IGN {
+        unsafe {
+            if PREVIOUS_SIGINFO[slot].load(Ordering::Acquire) {
+                let previous: extern "C" fn(c_int, *mut siginfo_t, *mut c_void) = mem::transmute(previous);
+                previous(signo, info, context);
+            } else {
+                let previous: extern "C" fn(c_int) = mem::transmute(previous);
+                previous(signo);
+            }
+        }
+    }
+
+    if let (Some(errno), Some(saved)) = (errno, saved) {
+        unsafe { *errno = saved }
+    }
+}
+
+// returns the action it replaced.
+fn install(signo: c_int) -> io::Result<libc::sigaction> {
+    unsafe {
+        let mut previous: libc::sigaction = mem::zeroed();
+        if libc::sigaction(signo, ::std::ptr::null(), &mut previous) != 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        let slot = signo as usize;
+        PREVIOUS_SIGINFO[slot].store(previous.sa_flags & libc::SA_SIGINFO != 0, Ordering::Release);
+        PREVIOUS[slot].store(previous.sa_sigaction, Ordering::Release);
+
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = handle as extern "C" fn(c_int, *mut siginfo_t, *mut c_void) as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+
+        if libc::sigaction(signo, &action, ::std::ptr::null_mut()) != 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        Ok(previous)
+    }
+}
+
+// run `callback` on the watcher thread each time `signo` is delivered, for
+// as long as it returns true.
+pub(crate) fn watch<F: FnMut() -> bool + Send + 'static>(signo: c_int, callback: F) -> io::Result<()> {
+    if signo <= 0 || signo > MAX_SIGNAL {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "signal number out of range"))
+    }
+
+    let watcher = watcher()?;
+    let mut callbacks = watcher.callbacks.lock();
+    callbacks.push((signo as u32, Box::new(callback)));
+
+    // under the lock, so concurrent callers can't install twice.
+    if !INSTALLED[signo as usize].load(Ordering::Acquire) {
+        match install(signo) {
+            Ok(previous) => watcher.replaced.lock().push((signo, previous)),
+            Err(err) => {
+                callbacks.pop();
+                return Err(err)
+            }
+        }
+        INSTALLED[signo as usize].store(true, Ordering::Release);
+    }
+
+    Ok(())
+}
+
+/// Fire `signal` each time one of `signals` is delivered to the process.
+///
+/// The binding holds the signal weakly, and is dropped once every handle to
+/// it is gone. Fails if a signal number can't be handled, such as `SIGKILL`,
+/// though any signals before it in `signals` stay bound.
+pub fn bind<T: Default + Send + Sync + 'static>(signal: &Signal<T>, signals: &[c_int]) -> io::Result<()> {
+    for &signo in signals {
+        watch(signo, fire_weakly(signal))?;
+    }
+
+    Ok(())
+}
+
+// fires the signal for as long as it's alive.
+fn fire_weakly<T: Default + Send + Sync + 'static>(signal: &Signal<T>) -> impl FnMut() -> bool + Send + 'static {
+    let weak = signal.downgrade();
+    move || match weak.shared.upgrade() {
+        Some(shared) => {
+            let _ = shared.set(Some(T::default()));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Handle which can fire a `Signal` from within a POSIX signal handler,
+/// for use in handlers installed with `sigaction` directly.
+///
+/// `fire_from_signal_handler` is async-signal-safe: it only writes to a
+/// pipe, and the signal is fired shortly after on a watcher thread. Like
+/// `bind`, the handle holds the signal weakly.
+#[derive(Debug, Clone, Copy)]
+pub struct HandlerSignal {
+    key: u32,
+}
+
+impl HandlerSignal {
+    /// Register `signal`, starting the watcher thread if needed. This isn't
+    /// async-signal-safe, so call it before installing the handler.
+    pub fn new<T: Default + Send + Sync + 'static>(signal: &Signal<T>) -> io::Result<HandlerSignal> {
+        let watcher = watcher()?;
+        let key = NEXT_HANDLER.fetch_add(1, Ordering::Relaxed);
+        watcher.callbacks.lock().push((key, Box::new(fire_weakly(signal))));
+
+        Ok(HandlerSignal { key })
+    }
+
+    /// Fire the signal. Async-signal-safe.
+    pub fn fire_from_signal_handler(&self) {
+        send(self.key)
+    }
+}
+
+/// An eventfd which becomes readable once an exit fires, for poll and epoll
+/// loops. Created by `Exit::eventfd`.
+///
+/// Reading it clears it until the signal is reset and fires again. Once
+/// dropped, the signal no longer writes to it.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct ExitFd {
+    // unregistered before the fd is closed.
+    _hook: FireHook,
+    fd: Arc<OwnedFd>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AsRawFd for ExitFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<T: Send + 'static> Exit<T> {
+    /// Get an eventfd which becomes readable once the signal fires.
+    pub fn eventfd(&self) -> io::Result<ExitFd> {
+        let raw = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if raw < 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        let fd = Arc::new(unsafe { OwnedFd::from_raw_fd(raw) });
+        let weak = Arc::downgrade(&fd);
+        let hook = Shared::hook(&self.shared, Box::new(move |_: Option<&T>| match weak.upgrade() {
+            Some(fd) => {
+                let one = 1u64.to_ne_bytes();
+                unsafe {
+                    libc::write(fd.as_raw_fd(), one.as_ptr() as *const c_void, one.len());
+                }
+                true
+            }
+            None => false,
+        }));
+
+        Ok(ExitFd { _hook: hook, fd })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::*;
+
+    #[test]
+    fn ctrl_c() {
+        let exit = ::Exit::on_ctrl_c().unwrap();
+        assert!(exit.is_live());
+
+        unsafe {
+            libc::raise(libc::SIGINT);
+        }
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+
+        // the default action is back, for a second Ctrl-C to terminate.
+        let start = Instant::now();
+        while disposition(libc::SIGINT) != libc::SIG_DFL {
+            assert!(start.elapsed() < Duration::from_millis(5000));
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn disposition(signo: c_int) -> libc::sighandler_t {
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            assert_eq!(libc::sigaction(signo, ::std::ptr::null(), &mut action), 0);
+            action.sa_sigaction
+        }
+    }
+
+    #[test]
+    fn binds_signals() {
+        let (signal, exit) = ::signal();
+        bind(&signal, &[libc::SIGUSR1]).unwrap();
+
+        unsafe {
+            libc::raise(libc::SIGUSR1);
+        }
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+
+        assert!(signal.reset());
+        let exit = signal.make_exit();
+        unsafe {
+            libc::raise(libc::SIGUSR1);
+        }
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+
+    #[test]
+    fn chains_previous_handler() {
+        static CALLED: AtomicBool = AtomicBool::new(false);
+        extern "C" fn previous(_: c_int) {
+            CALLED.store(true, Ordering::SeqCst);
+        }
+
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = previous as extern "C" fn(c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            assert_eq!(libc::sigaction(libc::SIGUSR2, &action, ::std::ptr::null_mut()), 0);
+        }
+
+        let (signal, exit) = ::signal();
+        bind(&signal, &[libc::SIGUSR2]).unwrap();
+        unsafe {
+            libc::raise(libc::SIGUSR2);
+        }
+
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+        assert!(CALLED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn fire_from_signal_handler() {
+        static HANDLE: OnceLock<HandlerSignal> = OnceLock::new();
+        extern "C" fn handler(_: c_int) {
+            if let Some(handle) = HANDLE.get() {
+                handle.fire_from_signal_handler();
+            }
+        }
+
+        let (signal, exit) = ::signal();
+        HANDLE.set(HandlerSignal::new(&signal).unwrap()).unwrap();
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handler as extern "C" fn(c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            assert_eq!(libc::sigaction(libc::SIGWINCH, &action, ::std::ptr::null_mut()), 0);
+            libc::raise(libc::SIGWINCH);
+        }
+
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn eventfd_readable_on_fire() {
+        let readable = |fd: &ExitFd| {
+            let mut poll = libc::pollfd { fd: fd.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            unsafe { libc::poll(&mut poll, 1, 0) == 1 }
+        };
+
+        let (signal, exit) = ::signal();
+        let fd = exit.eventfd().unwrap();
+        assert!(!readable(&fd));
+
+        signal.fire().unwrap();
+        assert!(readable(&fd));
+        assert!(readable(&exit.eventfd().unwrap()));
+
+        signal.reset();
+        drop(fd);
+        assert!(exit.shared.waiting.lock().hooks.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_signals() {
+        let signal = ::signal_only();
+        assert!(bind(&signal, &[0]).is_err());
+        assert!(bind(&signal, &[libc::SIGKILL]).is_err());
+    }
+}
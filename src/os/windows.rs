@@ -0,0 +1,206 @@
+//! Binding Windows console control events and service stops to a `Signal`.
+//!
+//! Console events are delivered on a thread created by the system, so the
+//! handler fires signals directly. For `CTRL_CLOSE_EVENT` and the events
+//! after it, the system ends the process as soon as the handler returns, so
+//! the handler waits for the shutdown to complete first, for as long as the
+//! system allows.
+
+use parking_lot::Mutex;
+
+use std::io;
+use std::os::raw::c_void;
+use std::os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle, RawHandle};
+use std::ptr;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use {Exit, FireHook, Shared, Signal, WeakSignal};
+
+type Bool = i32;
+type Dword = u32;
+
+/// Ctrl-C was pressed.
+pub const CTRL_C_EVENT: u32 = 0;
+/// Ctrl-Break was pressed.
+pub const CTRL_BREAK_EVENT: u32 = 1;
+/// The console window is being closed.
+pub const CTRL_CLOSE_EVENT: u32 = 2;
+/// The user is logging off. Only delivered to services.
+pub const CTRL_LOGOFF_EVENT: u32 = 5;
+/// The system is shutting down. Only delivered to services.
+pub const CTRL_SHUTDOWN_EVENT: u32 = 6;
+
+/// The service control manager asks the service to stop.
+pub const SERVICE_CONTROL_STOP: u32 = 0x1;
+/// The system is shutting down.
+pub const SERVICE_CONTROL_SHUTDOWN: u32 = 0x5;
+/// The system is about to shut down, for services that registered for it.
+pub const SERVICE_CONTROL_PRESHUTDOWN: u32 = 0xf;
+
+/// How long the system waits by default for the handler of the events that
+/// end the process, after which it ends it anyway.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+// whether the system ends the process once the handler returns.
+fn ends_process(event: u32) -> bool {
+    matches!(event, CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT)
+}
+
+/// The console events that conventionally ask a process to shut down.
+pub const TERMINATION_EVENTS: [u32; 3] = [CTRL_C_EVENT, CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT];
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetConsoleCtrlHandler(handler: Option<unsafe extern "system" fn(Dword) -> Bool>, add: Bool) -> Bool;
+    fn CreateEventW(attributes: *mut c_void, manual_reset: Bool, initial_state: Bool, name: *const u16) -> RawHandle;
+    fn SetEvent(event: RawHandle) -> Bool;
+}
+
+// run on each delivery. returns whether to keep it.
+type Callback = Box<dyn FnMut() -> bool + Send>;
+type Handlers = Mutex<Vec<(u32, Callback)>>;
+
+static HANDLERS: OnceLock<io::Result<Handlers>> = OnceLock::new();
+
+fn handlers() -> io::Result<&'static Handlers> {
+    let installed = HANDLERS.get_or_init(|| {
+        if unsafe { SetConsoleCtrlHandler(Some(handle), 1) } == 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        Ok(Mutex::new(Vec::new()))
+    });
+
+    match *installed {
+        Ok(ref handlers) => Ok(handlers),
+        Err(ref err) => Err(io::Error::new(err.kind(), err.to_string())),
+    }
+}
+
+unsafe extern "system" fn handle(event: Dword) -> Bool {
+    let handlers = match HANDLERS.get() {
+        Some(Ok(handlers)) => handlers,
+        _ => return 0,
+    };
+
+    let mut handled = false;
+    handlers.lock().retain_mut(|&mut (bound, ref mut callback)| {
+        if bound != event {
+            return true
+        }
+
+        handled = true;
+        callback()
+    });
+
+    handled as Bool
+}
+
+// run `callback` each time the console control `event` is delivered, for as
+// long as it returns true.
+pub(crate) fn watch<F: FnMut() -> bool + Send + 'static>(event: u32, callback: F) -> io::Result<()> {
+    handlers()?.lock().push((event, Box::new(callback)));
+    Ok(())
+}
+
+/// Fire `signal` each time one of the console control `events` is delivered
+/// to the process, stopping the default handling of those events.
+///
+/// For `CTRL_CLOSE_EVENT`, `CTRL_LOGOFF_EVENT` and `CTRL_SHUTDOWN_EVENT`,
+/// after which the system ends the process, delivery then blocks until
+/// every `Exit` of the signal is dropped, for up to `SHUTDOWN_GRACE`.
+///
+/// Like `os::unix::bind`, the binding holds the signal weakly.
+pub fn bind<T: Default + Send + Sync + 'static>(signal: &Signal<T>, events: &[u32]) -> io::Result<()> {
+    for &event in events {
+        let weak = signal.downgrade();
+        watch(event, move || match weak.shared.upgrade() {
+            Some(shared) => {
+                let _ = shared.set(Some(T::default()));
+                if ends_process(event) {
+                    let _ = shared.wait_idle_until(Instant::now().checked_add(SHUTDOWN_GRACE));
+                }
+                true
+            }
+            None => false,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Fires a signal on the stop and shutdown events of the service control
+/// manager, for calling from a service's control handler.
+pub struct ServiceStop<T = ()> {
+    signal: WeakSignal<T>,
+}
+
+impl<T: Default> ServiceStop<T> {
+    /// Fire `signal` on service stop, holding it weakly.
+    pub fn new(signal: &Signal<T>) -> ServiceStop<T> {
+        ServiceStop { signal: signal.downgrade() }
+    }
+
+    /// Handle a service control code, firing the signal on
+    /// `SERVICE_CONTROL_STOP`, `SERVICE_CONTROL_SHUTDOWN` and
+    /// `SERVICE_CONTROL_PRESHUTDOWN`. Returns whether the code was handled.
+    pub fn handle(&self, control: u32) -> bool {
+        match control {
+            SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN | SERVICE_CONTROL_PRESHUTDOWN => {
+                let _ = self.signal.fire();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<T> Clone for ServiceStop<T> {
+    fn clone(&self) -> ServiceStop<T> {
+        ServiceStop { signal: self.signal.clone() }
+    }
+}
+
+/// An event object which is signaled once an exit fires, for
+/// `WaitForMultipleObjects` and friends. Created by `Exit::event`.
+///
+/// Once dropped, the signal no longer sets it.
+pub struct ExitEvent {
+    // unregistered before the handle is closed.
+    _hook: FireHook,
+    handle: Arc<OwnedHandle>,
+}
+
+impl AsRawHandle for ExitEvent {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.handle.as_raw_handle()
+    }
+}
+
+impl<T: Send + 'static> Exit<T> {
+    /// Get an event object which is signaled once the signal fires.
+    ///
+    /// A manual-reset event stays signaled until reset with `ResetEvent`. An
+    /// auto-reset one releases a single waiting thread and then resets.
+    pub fn event(&self, manual_reset: bool) -> io::Result<ExitEvent> {
+        let raw = unsafe { CreateEventW(ptr::null_mut(), manual_reset as Bool, 0, ptr::null()) };
+        if raw.is_null() {
+            return Err(io::Error::last_os_error())
+        }
+
+        let handle = Arc::new(unsafe { OwnedHandle::from_raw_handle(raw) });
+        let weak = Arc::downgrade(&handle);
+        let hook = Shared::hook(&self.shared, Box::new(move |_: Option<&T>| match weak.upgrade() {
+            Some(handle) => {
+                unsafe {
+                    SetEvent(handle.as_raw_handle());
+                }
+                true
+            }
+            None => false,
+        }));
+
+        Ok(ExitEvent { _hook: hook, handle })
+    }
+}
@@ -0,0 +1,18 @@
+use std::panic;
+
+use Signal;
+
+/// Fire `signal` whenever any thread panics, so a panicking background
+/// thread brings the rest of the process down with it.
+///
+/// Chains to the panic hook that was installed before, running it first.
+/// The hook holds the signal weakly, like `Signal::downgrade`.
+pub fn install_panic_hook<T: Default + Send + Sync + 'static>(signal: &Signal<T>) {
+    let signal = signal.downgrade();
+    let previous = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let _ = signal.fire();
+    }));
+}
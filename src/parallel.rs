@@ -0,0 +1,86 @@
+//! Cooperative cancellation of `rayon` parallel work. Long loops check the
+//! exit with `Exit::checkpoint`, and divide-and-conquer jobs split with
+//! `Exit::join_until`, so work is abandoned once shutdown arrives.
+
+use rayon;
+
+use {Cancelled, Exit};
+
+impl<T> Exit<T> {
+    /// Fail with `Cancelled` if the signal has fired, for `?` in loops
+    /// such as rayon's `try_for_each`.
+    ///
+    /// This is a single atomic load, cheap enough to call on every item.
+    pub fn checkpoint(&self) -> Result<(), Cancelled> {
+        if self.is_live() {
+            Ok(())
+        } else {
+            Err(Cancelled)
+        }
+    }
+}
+
+impl<T: Send + Sync> Exit<T> {
+    /// Run `a` and `b` in parallel with `rayon::join`, failing with
+    /// `Cancelled` if either does.
+    ///
+    /// Neither closure is started once the signal has fired, so nested calls
+    /// stop splitting work as soon as shutdown arrives.
+    pub fn join_until<A, B, RA, RB>(&self, a: A, b: B) -> Result<(RA, RB), Cancelled>
+        where A: FnOnce() -> Result<RA, Cancelled> + Send,
+              B: FnOnce() -> Result<RB, Cancelled> + Send,
+              RA: Send,
+              RB: Send,
+    {
+        self.checkpoint()?;
+
+        let (a, b) = rayon::join(
+            || self.checkpoint().and_then(|()| a()),
+            || self.checkpoint().and_then(|()| b()),
+        );
+        Ok((a?, b?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+    use super::*;
+
+    fn sum(exit: &Exit, xs: &[u64]) -> Result<u64, Cancelled> {
+        if xs.len() <= 16 {
+            exit.checkpoint()?;
+            return Ok(xs.iter().sum())
+        }
+
+        let (left, right) = xs.split_at(xs.len() / 2);
+        let (a, b) = exit.join_until(|| sum(exit, left), || sum(exit, right))?;
+        Ok(a + b)
+    }
+
+    #[test]
+    fn join_until_completes() {
+        let (_signal, exit) = ::signal();
+        let xs: Vec<u64> = (0..1000).collect();
+
+        assert_eq!(sum(&exit, &xs), Ok(499500));
+    }
+
+    #[test]
+    fn join_until_cancelled() {
+        let (signal, exit) = ::signal();
+        let xs: Vec<u64> = (0..1000).collect();
+        signal.fire().unwrap();
+
+        assert_eq!(sum(&exit, &xs), Err(Cancelled));
+    }
+
+    #[test]
+    fn checkpoint_in_parallel_loop() {
+        let (signal, exit) = ::signal();
+        assert_eq!((0..100).into_par_iter().try_for_each(|_| exit.checkpoint()), Ok(()));
+
+        signal.fire().unwrap();
+        assert_eq!((0..100).into_par_iter().try_for_each(|_| exit.checkpoint()), Err(Cancelled));
+    }
+}
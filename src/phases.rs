@@ -0,0 +1,140 @@
+use futures::prelude::*;
+
+use std::collections::{BTreeMap, VecDeque};
+
+use {Exit, Signal, Tracker, TrackerToken, TrackerWait};
+
+// a signal and the registrants which must finish before moving on.
+pub(crate) struct Stage {
+    signal: Signal,
+    tracker: Tracker,
+}
+
+impl Stage {
+    pub(crate) fn new() -> Stage {
+        Stage { signal: ::signal_only(), tracker: Tracker::new() }
+    }
+
+    pub(crate) fn register(&self) -> (Exit, TrackerToken) {
+        (self.signal.make_exit(), self.tracker.token())
+    }
+}
+
+/// Orchestrates a shutdown in numbered phases.
+///
+/// Registrants of a phase get an exit and a token. Shutdown fires the phases
+/// in ascending order, only moving on to the next phase once every token of
+/// the previous one has been dropped. Dropping the `Phases` without calling
+/// `shutdown` fires every phase at once.
+#[derive(Default)]
+pub struct Phases {
+    phases: BTreeMap<u32, Stage>,
+}
+
+impl Phases {
+    /// Create an orchestrator with no phases.
+    pub fn new() -> Phases {
+        Phases::default()
+    }
+
+    /// Register in `phase`, getting an exit which fires when the phase
+    /// starts, and a token to drop once the registrant has finished.
+    pub fn register(&mut self, phase: u32) -> (Exit, TrackerToken) {
+        self.phases.entry(phase).or_insert_with(Stage::new).register()
+    }
+
+    /// Start the shutdown, getting a future which resolves once every phase
+    /// has completed.
+    pub fn shutdown(self) -> PhasedShutdown {
+        PhasedShutdown::new(self.phases.into_values().collect())
+    }
+}
+
+/// Future driving a shutdown through its phases in order.
+pub struct PhasedShutdown {
+    pending: VecDeque<Stage>,
+    current: Option<TrackerWait>,
+}
+
+impl PhasedShutdown {
+    pub(crate) fn new(stages: VecDeque<Stage>) -> PhasedShutdown {
+        PhasedShutdown { pending: stages, current: None }
+    }
+}
+
+impl Future for PhasedShutdown {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        loop {
+            if let Some(ref mut current) = self.current {
+                try_ready!(current.poll());
+            }
+
+            match self.pending.pop_front() {
+                Some(stage) => {
+                    let _ = stage.signal.fire();
+                    self.current = Some(stage.tracker.wait());
+                }
+                None => {
+                    self.current = None;
+                    return Ok(Async::Ready(()))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn phases_run_in_order() {
+        let mut phases = Phases::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let handles: Vec<_> = [2, 0, 1, 0].iter().map(|&phase| {
+            let (exit, token) = phases.register(phase);
+            let log = log.clone();
+            thread::spawn(move || {
+                exit.wait().unwrap();
+                log.lock().unwrap().push(phase);
+                drop(token);
+            })
+        }).collect();
+
+        phases.shutdown().wait().unwrap();
+        assert_eq!(*log.lock().unwrap(), vec![0, 0, 1, 2]);
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn waits_for_previous_phase() {
+        let mut phases = Phases::new();
+        let (_first, token) = phases.register(0);
+        let (second, _) = phases.register(1);
+
+        let mut shutdown = phases.shutdown();
+        ::futures::future::lazy(move || {
+            assert_eq!(shutdown.poll(), Ok(Async::NotReady));
+            assert!(second.is_live());
+
+            drop(token);
+            assert_eq!(shutdown.poll(), Ok(Async::Ready(())));
+            assert!(!second.is_live());
+            ::futures::future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn no_phases() {
+        assert_eq!(Phases::new().shutdown().wait(), Ok(()));
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::vec::Vec;
+
+use {Exit, Signal};
+
+struct Votes {
+    cast: AtomicUsize,
+    needed: usize,
+    signal: Signal,
+}
+
+impl Votes {
+    // returns whether the quorum has been reached.
+    fn vote(&self) -> bool {
+        let cast = self.cast.fetch_add(1, Ordering::AcqRel) + 1;
+        if cast == self.needed {
+            let _ = self.signal.fire();
+        }
+
+        cast >= self.needed
+    }
+}
+
+/// One of the handles of a quorum signal, created by `quorum`.
+///
+/// Each handle votes once, either by `fire` or by being dropped.
+pub struct QuorumSignal {
+    votes: Arc<Votes>,
+    voted: AtomicBool,
+}
+
+impl QuorumSignal {
+    /// Cast this handle's vote, returning whether the quorum has been
+    /// reached. Firing the same handle again doesn't count twice.
+    pub fn fire(&self) -> bool {
+        if self.voted.swap(true, Ordering::AcqRel) {
+            return self.is_fired()
+        }
+
+        self.votes.vote()
+    }
+
+    /// Whether the quorum has been reached.
+    pub fn is_fired(&self) -> bool {
+        self.votes.signal.is_fired()
+    }
+
+    /// Get an exit future, which resolves once the quorum is reached.
+    pub fn make_exit(&self) -> Exit {
+        self.votes.signal.make_exit()
+    }
+}
+
+impl Drop for QuorumSignal {
+    fn drop(&mut self) {
+        let _ = self.fire();
+    }
+}
+
+/// Create `n` signal handles and an exit which fires once `k` of them have
+/// fired or been dropped.
+///
+/// Panics if `k > n`, as the exit could never fire.
+pub fn quorum(n: usize, k: usize) -> (Vec<QuorumSignal>, Exit) {
+    assert!(k <= n, "quorum of {} can't be reached with {} handles", k, n);
+
+    let (signal, exit) = ::signal();
+    if k == 0 {
+        let _ = signal.fire();
+    }
+
+    let votes = Arc::new(Votes { cast: AtomicUsize::new(0), needed: k, signal });
+    let handles = (0..n)
+        .map(|_| QuorumSignal { votes: votes.clone(), voted: AtomicBool::new(false) })
+        .collect();
+
+    (handles, exit)
+}
+
+/// Create `n` signal handles and an exit which fires only once every one of
+/// them has fired or been dropped.
+pub fn barrier(n: usize) -> (Vec<QuorumSignal>, Exit) {
+    quorum(n, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_at_quorum() {
+        let (mut handles, exit) = quorum(3, 2);
+        let c = handles.pop().unwrap();
+        let b = handles.pop().unwrap();
+        let a = handles.pop().unwrap();
+
+        assert!(!a.fire());
+        assert!(!a.fire());
+        assert!(exit.is_live());
+
+        drop(b);
+        assert!(!exit.is_live());
+        assert!(c.is_fired());
+        assert!(c.fire());
+        assert!(::futures::Future::wait(exit).is_ok());
+    }
+
+    #[test]
+    fn barrier_waits_for_all() {
+        let (handles, exit) = barrier(3);
+
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert!(exit.is_live());
+            if i % 2 == 0 {
+                handle.fire();
+            } else {
+                drop(handle);
+            }
+        }
+
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    fn zero_quorum_fires_immediately() {
+        let (_handles, exit) = quorum(2, 0);
+        assert!(!exit.is_live());
+    }
+
+    #[test]
+    #[should_panic]
+    fn unreachable_quorum() {
+        let _ = quorum(1, 2);
+    }
+}
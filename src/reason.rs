@@ -0,0 +1,99 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+/// A structured reason for shutting down, for use with `signal_with`.
+///
+/// The default reason, which exits resolve to when the signal is dropped, has
+/// no message, code or source.
+#[derive(Debug, Clone, Default)]
+pub struct ExitReason {
+    message: Cow<'static, str>,
+    code: Option<i32>,
+    source: Option<Arc<dyn Error + Send + Sync>>,
+}
+
+impl ExitReason {
+    /// Create a reason with the given message.
+    pub fn new<M: Into<Cow<'static, str>>>(message: M) -> ExitReason {
+        ExitReason { message: message.into(), code: None, source: None }
+    }
+
+    /// Attach a process exit code.
+    pub fn with_code(mut self, code: i32) -> ExitReason {
+        self.code = Some(code);
+        self
+    }
+
+    /// Attach the error that caused the shutdown.
+    pub fn with_source<E: Error + Send + Sync + 'static>(mut self, source: E) -> ExitReason {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// The message describing the shutdown.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The process exit code, if any.
+    pub fn code(&self) -> Option<i32> {
+        self.code
+    }
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.message.is_empty() {
+            f.write_str("exit signal dropped")?;
+        } else {
+            f.write_str(&self.message)?;
+        }
+
+        match self.code {
+            Some(code) => write!(f, " (exit code {})", code),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Error for ExitReason {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|e| &**e as &(dyn Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+    use std::error::Error;
+    use std::io;
+    use super::*;
+
+    #[test]
+    fn reason_is_broadcast() {
+        let (signal, exit) = ::signal_with::<ExitReason>();
+        let io_err = io::Error::other("disk full");
+        let reason = ExitReason::new("fatal error").with_code(2).with_source(io_err);
+
+        signal.fire_with(reason).unwrap();
+
+        let reason = Future::wait(exit).unwrap();
+        assert_eq!(reason.message(), "fatal error");
+        assert_eq!(reason.code(), Some(2));
+        assert_eq!(reason.to_string(), "fatal error (exit code 2)");
+        assert_eq!(reason.source().unwrap().to_string(), "disk full");
+    }
+
+    #[test]
+    fn dropped_reason() {
+        let (signal, exit) = ::signal_with::<ExitReason>();
+        drop(signal);
+
+        let reason = Future::wait(exit).unwrap();
+        assert_eq!(reason.code(), None);
+        assert!(reason.source().is_none());
+        assert_eq!(reason.to_string(), "exit signal dropped");
+    }
+}
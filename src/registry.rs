@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use std::vec::Vec;
+
+use lock::Mutex;
+use {Exit, Signal};
+
+/// Process-wide registry of named signals, one per subsystem that can be
+/// shut down on its own. Obtained through `registry`.
+///
+/// Signals are created on first use under each name. The registry keeps a
+/// handle to each, so dropping the handles it returns never fires them.
+pub struct SignalRegistry {
+    signals: Mutex<BTreeMap<&'static str, Signal>>,
+}
+
+impl SignalRegistry {
+    fn get(&self, name: &'static str) -> Signal {
+        self.signals.lock().entry(name).or_insert_with(::signal_only).clone()
+    }
+
+    /// Get the signal named `name` and an exit for it.
+    pub fn pair(&self, name: &'static str) -> (Signal, Exit) {
+        let signal = self.get(name);
+        let exit = signal.make_exit();
+
+        (signal, exit)
+    }
+
+    /// Get the signal named `name`.
+    pub fn signal(&self, name: &'static str) -> Signal {
+        self.get(name)
+    }
+
+    /// Get an exit for the signal named `name`.
+    pub fn exit(&self, name: &'static str) -> Exit {
+        self.get(name).make_exit()
+    }
+
+    /// The names of the signals created so far, in order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.signals.lock().keys().cloned().collect()
+    }
+}
+
+/// Get the process-wide registry of named signals.
+pub fn registry() -> &'static SignalRegistry {
+    static REGISTRY: OnceLock<SignalRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| SignalRegistry { signals: Mutex::new(BTreeMap::new()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_signals() {
+        let (db, db_exit) = registry().pair("registry-test-db");
+        let http_exit = registry().exit("registry-test-http");
+        drop(registry().signal("registry-test-db"));
+
+        db.fire().unwrap();
+        assert!(!registry().exit("registry-test-db").is_live());
+        assert!(!db_exit.is_live());
+        assert!(http_exit.is_live());
+
+        let names = registry().names();
+        assert!(names.contains(&"registry-test-db") && names.contains(&"registry-test-http"));
+    }
+}
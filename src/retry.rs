@@ -0,0 +1,162 @@
+use futures::prelude::*;
+
+use std::cmp;
+use std::time::{Duration, Instant};
+
+use timer::Delay;
+use {Cancelled, Exit};
+
+/// How `Exit::retry` spaces out attempts.
+///
+/// The delay starts at the initial one and is multiplied after every
+/// failure, up to a maximum. By default the delay doubles up to 30 seconds,
+/// and retries go on until the exit fires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    multiplier: u32,
+    max_retries: Option<u32>,
+}
+
+impl Backoff {
+    /// Back off starting with `initial` between the first two attempts.
+    pub fn new(initial: Duration) -> Backoff {
+        Backoff { initial, max: Duration::from_secs(30), multiplier: 2, max_retries: None }
+    }
+
+    /// Never wait longer than `max` between attempts.
+    pub fn max_delay(mut self, max: Duration) -> Backoff {
+        self.max = max;
+        self
+    }
+
+    /// Multiply the delay by `multiplier` after every failure.
+    pub fn multiplier(mut self, multiplier: u32) -> Backoff {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Give up after retrying `retries` times, failing with the last error.
+    pub fn max_retries(mut self, retries: u32) -> Backoff {
+        self.max_retries = Some(retries);
+        self
+    }
+}
+
+enum State<F> {
+    // the first attempt hasn't started yet.
+    Start,
+    Running(F),
+    Waiting(Delay),
+}
+
+/// Future that retries an operation with backoff until it succeeds, the
+/// policy gives up, or the exit signal fires. Created by `Exit::retry`.
+///
+/// It resolves to `Err(Cancelled)` if the exit fires first, dropping the
+/// attempt in flight, and fails with the last error if the policy gives up.
+pub struct Retry<G, F: IntoFuture, T = ()> {
+    exit: Exit<T>,
+    policy: Backoff,
+    operation: G,
+    state: State<F::Future>,
+    retries: u32,
+    delay: Duration,
+}
+
+impl<G, F: IntoFuture, T> Retry<G, F, T> {
+    pub(crate) fn new(exit: Exit<T>, policy: Backoff, operation: G) -> Retry<G, F, T> {
+        let delay = policy.initial;
+
+        Retry { exit, policy, operation, state: State::Start, retries: 0, delay }
+    }
+}
+
+impl<G, F, T> Future for Retry<G, F, T>
+    where G: FnMut() -> F, F: IntoFuture, T: Clone + Default
+{
+    type Item = Result<F::Item, Cancelled>;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            if self.exit.check().is_ready() {
+                return Ok(Async::Ready(Err(Cancelled)))
+            }
+
+            let next = match self.state {
+                State::Start => State::Running((self.operation)().into_future()),
+                State::Running(ref mut attempt) => match attempt.poll() {
+                    Ok(Async::Ready(item)) => return Ok(Async::Ready(Ok(item))),
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(e) => {
+                        if self.policy.max_retries.is_some_and(|max| self.retries >= max) {
+                            return Err(e)
+                        }
+
+                        self.retries += 1;
+                        let delay = cmp::min(self.delay, self.policy.max);
+                        self.delay = cmp::min(self.delay.saturating_mul(self.policy.multiplier), self.policy.max);
+
+                        // a delay too large to represent waits for the exit.
+                        match Instant::now().checked_add(delay) {
+                            Some(at) => State::Waiting(Delay::new(at)),
+                            None => return Ok(Async::NotReady),
+                        }
+                    }
+                },
+                State::Waiting(ref mut delay) => match delay.poll() {
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    _ => State::Running((self.operation)().into_future()),
+                },
+            };
+
+            self.state = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::future;
+    use super::*;
+
+    #[test]
+    fn retries_until_success() {
+        let (_signal, exit) = ::signal();
+        let mut attempts = 0;
+        let retry = exit.retry(Backoff::new(Duration::from_millis(1)), || {
+            attempts += 1;
+            if attempts < 3 { Err(attempts) } else { Ok(attempts) }
+        });
+
+        assert_eq!(retry.wait(), Ok(Ok(3)));
+    }
+
+    #[test]
+    fn gives_up_with_last_error() {
+        let (_signal, exit) = ::signal();
+        let policy = Backoff::new(Duration::from_millis(1)).max_retries(2);
+        let mut attempts = 0;
+        let retry = exit.retry(policy, || {
+            attempts += 1;
+            Err::<(), _>(attempts)
+        });
+
+        assert_eq!(retry.wait(), Err(3));
+    }
+
+    #[test]
+    fn cancelled_during_backoff() {
+        let (signal, exit) = ::signal();
+        let mut retry = exit.retry(Backoff::new(Duration::from_secs(3600)), || Err::<(), ()>(()));
+
+        future::lazy(move || {
+            assert_eq!(retry.poll(), Ok(Async::NotReady));
+            signal.fire().unwrap();
+            assert_eq!(retry.poll(), Ok(Async::Ready(Err(Cancelled))));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+//! Serving axum routers until an exit fires, draining their connections,
+//! and handing the exit to handlers such as long-polling or SSE endpoints.
+
+use axum::extract::{FromRequestParts, Request};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::serve::{self, IncomingStream, Listener};
+use axum::{Extension, Router};
+use tower_service::Service;
+
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::future::{self, Future, IntoFuture, Ready};
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use hyper::{Drain, GracefulShutdown, ShutdownSignal};
+use {Exit, Tracker, TrackerToken};
+
+/// Rejection for handlers taking an `Exit` from a router not served by
+/// `serve_until`, or not given one through an `Extension` layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingExit;
+
+impl IntoResponse for MissingExit {
+    fn into_response(self) -> Response {
+        (StatusCode::INTERNAL_SERVER_ERROR, "no exit for this router").into_response()
+    }
+}
+
+impl<S, T> FromRequestParts<S> for Exit<T>
+    where S: Send + Sync, T: Send + Sync + 'static
+{
+    type Rejection = MissingExit;
+
+    fn from_request_parts(parts: &mut Parts, _: &S) -> impl Future<Output = Result<Exit<T>, MissingExit>> + Send {
+        future::ready(parts.extensions.get::<Exit<T>>().cloned().ok_or(MissingExit))
+    }
+}
+
+// makes a service per connection, which counts as open until hyper drops it.
+#[derive(Clone)]
+struct TrackConnections {
+    router: Router,
+    tracker: Tracker,
+}
+
+impl<'a, L: Listener> Service<IncomingStream<'a, L>> for TrackConnections {
+    type Response = Connection;
+    type Error = Infallible;
+    type Future = Ready<Result<Connection, Infallible>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: IncomingStream<'a, L>) -> Self::Future {
+        let token = Arc::new(self.tracker.token());
+        future::ready(Ok(Connection { router: self.router.clone(), _token: token }))
+    }
+}
+
+#[derive(Clone)]
+struct Connection {
+    router: Router,
+    _token: Arc<TrackerToken>,
+}
+
+impl Service<Request> for Connection {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = <Router as Service<Request>>::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Service::<Request>::poll_ready(&mut self.router, cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.router.call(request)
+    }
+}
+
+/// Serve `router` on `listener` until the exit of `graceful` fires, then
+/// shut down gracefully, giving open connections up to `drain_timeout` to
+/// close. See `ServeUntil`.
+///
+/// Handlers can take the exit as an extractor.
+pub fn serve_until<L, T>(listener: L, router: Router, graceful: &GracefulShutdown<T>, drain_timeout: Duration) -> ServeUntil<T>
+    where L: Listener, L::Addr: Debug, T: Clone + Default + Send + Sync + 'static
+{
+    let router = router.layer(Extension(graceful.exit.clone()));
+    let make_service = TrackConnections { router, tracker: graceful.tracker.clone() };
+    let serve = serve::serve(listener, make_service).with_graceful_shutdown(graceful.signal());
+
+    ServeUntil {
+        serve: Box::pin(serve.into_future()),
+        signal: graceful.signal(),
+        graceful: graceful.clone(),
+        drain_timeout,
+        drain: DrainState::Serving,
+    }
+}
+
+enum DrainState {
+    Serving,
+    Draining(Drain),
+    // the drain resolved, so it must not be polled again.
+    Drained,
+}
+
+/// Future serving an axum router until the exit fires and its connections
+/// drain. Created by `serve_until`.
+///
+/// It resolves to whether every connection closed before the drain
+/// timeout. Connections still open after it are left to finish on their
+/// own tasks.
+pub struct ServeUntil<T = ()> {
+    serve: Pin<Box<dyn Future<Output = io::Result<()>> + Send>>,
+    signal: ShutdownSignal<T>,
+    graceful: GracefulShutdown<T>,
+    drain_timeout: Duration,
+    drain: DrainState,
+}
+
+impl<T: Clone + Default> Future for ServeUntil<T> {
+    type Output = io::Result<bool>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+        if let Poll::Ready(res) = self.serve.as_mut().poll(cx) {
+            return Poll::Ready(res.map(|()| true))
+        }
+
+        if let DrainState::Serving = self.drain {
+            if Pin::new(&mut self.signal).poll(cx).is_ready() {
+                self.drain = DrainState::Draining(self.graceful.drain(self.drain_timeout));
+            }
+        }
+
+        let drained = match self.drain {
+            DrainState::Draining(ref mut drain) => match Pin::new(drain).poll(cx) {
+                Poll::Ready(drained) => drained,
+                Poll::Pending => return Poll::Pending,
+            },
+            _ => return Poll::Pending,
+        };
+
+        // once drained, the server finishes on its own.
+        self.drain = DrainState::Drained;
+        if drained {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(false))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::get;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn serves_until_exit() {
+        let (signal, exit) = ::signal();
+        let graceful = GracefulShutdown::new(&exit);
+        let router = Router::new().route("/", get(|exit: Exit| future::ready(if exit.is_live() { "live" } else { "fired" })));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"GET / HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n").unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            signal.fire().unwrap();
+            response
+        });
+
+        let runtime = ::tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let listener = {
+            let _runtime = runtime.enter();
+            ::tokio::net::TcpListener::from_std(listener).unwrap()
+        };
+        let drained = runtime.block_on(serve_until(listener, router, &graceful, Duration::from_secs(5)));
+
+        assert!(client.join().unwrap().ends_with("live"));
+        assert!(drained.unwrap());
+        assert_eq!(graceful.connections(), 0);
+    }
+
+    #[test]
+    fn pending_after_draining() {
+        let (signal, exit) = ::signal();
+        let graceful = GracefulShutdown::new(&exit);
+        // a server still shutting down once its connections are gone.
+        let mut serving = ServeUntil {
+            serve: Box::pin(future::pending()),
+            signal: graceful.signal(),
+            graceful: graceful.clone(),
+            drain_timeout: Duration::from_secs(5),
+            drain: DrainState::Serving,
+        };
+        signal.fire().unwrap();
+
+        let mut cx = Context::from_waker(::std::task::Waker::noop());
+        for _ in 0..3 {
+            assert!(Pin::new(&mut serving).poll(&mut cx).is_pending());
+        }
+    }
+}
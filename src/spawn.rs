@@ -0,0 +1,159 @@
+//! Tying tokio tasks to an exit, so they're cancelled once it fires rather
+//! than left running.
+
+use futures::executor;
+use futures_util::future::{self, Either, FutureExt};
+use tokio::task::{self, JoinError, JoinHandle};
+
+use std::future::Future as StdFuture;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use compat01::Compat01;
+use {Exit, FireHook, Shared};
+
+impl<T: Send + 'static> Exit<T> {
+    /// Abort the task behind `handle` once the signal fires, or right away
+    /// if it already has, returning a future for its join result.
+    ///
+    /// Only the first fire is observed: the task stays aborted after a
+    /// reset.
+    pub fn abort_task_on_exit<R>(&self, handle: JoinHandle<R>) -> ExitJoinHandle<R> {
+        let abort = handle.abort_handle();
+        let hook = Shared::hook(&self.shared, Box::new(move |_| {
+            abort.abort();
+            false
+        }));
+
+        ExitJoinHandle { handle, hook: Some(hook) }
+    }
+}
+
+impl<T: Clone + Default + Send + Sync + 'static> Exit<T> {
+    /// Spawn `work` on the current tokio runtime, running it until complete
+    /// or the exit fires, like `until`. The handle's output is `None` if the
+    /// exit fired first, in which case the work is dropped.
+    ///
+    /// Like `until`, the work is polled before the exit.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside of a tokio runtime, like `tokio::spawn`.
+    pub fn spawn_until<F>(&self, work: F) -> JoinHandle<Option<F::Output>>
+        where F: StdFuture + Send + 'static, F::Output: Send + 'static
+    {
+        let exit = Compat01(executor::spawn(self.clone()));
+        task::spawn(future::select(Box::pin(work), exit).map(|either| match either {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }))
+    }
+}
+
+/// Future resolving to the join result of a tokio task which is aborted
+/// once an exit fires. Created by `Exit::abort_task_on_exit`.
+///
+/// Dropping it detaches the task, which is still aborted once the exit
+/// fires unless it finished first.
+pub struct ExitJoinHandle<R> {
+    handle: JoinHandle<R>,
+    // taken when dropped before the task finished.
+    hook: Option<FireHook>,
+}
+
+impl<R> ExitJoinHandle<R> {
+    /// Abort the task without waiting for the exit.
+    pub fn abort(&self) {
+        self.handle.abort()
+    }
+
+    /// Whether the task has finished, including by being aborted.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+impl<R> StdFuture for ExitJoinHandle<R> {
+    type Output = Result<R, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<R, JoinError>> {
+        Pin::new(&mut self.handle).poll(cx)
+    }
+}
+
+impl<R> Drop for ExitJoinHandle<R> {
+    fn drop(&mut self) {
+        if !self.handle.is_finished() {
+            if let Some(hook) = self.hook.take() {
+                hook.forget();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::runtime::{Builder, Runtime};
+    use tokio::sync::oneshot;
+    use std::future;
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    #[test]
+    fn spawns_until_exit() {
+        let runtime = runtime();
+        let _entered = runtime.enter();
+        let (signal, exit) = ::signal();
+
+        let done = exit.spawn_until(future::ready(5));
+        assert_eq!(runtime.block_on(done).unwrap(), Some(5));
+
+        let (tx, rx) = oneshot::channel::<()>();
+        let stopped = exit.spawn_until(hold(tx));
+        signal.fire().unwrap();
+        assert_eq!(runtime.block_on(stopped).unwrap(), None);
+        // the work is dropped along with the sender.
+        assert!(runtime.block_on(rx).is_err());
+    }
+
+    #[test]
+    fn aborts_on_fire() {
+        let runtime = runtime();
+        let (signal, exit) = ::signal();
+        let task = exit.abort_task_on_exit(runtime.spawn(future::pending::<()>()));
+
+        signal.fire().unwrap();
+        assert!(runtime.block_on(task).unwrap_err().is_cancelled());
+    }
+
+    #[test]
+    fn finished_tasks_unregister() {
+        let runtime = runtime();
+        let (_signal, exit) = ::signal();
+        let task = exit.abort_task_on_exit(runtime.spawn(future::ready(5)));
+        assert_eq!(runtime.block_on(task).unwrap(), 5);
+        assert!(exit.shared.waiting.lock().hooks.is_empty());
+    }
+
+    #[test]
+    fn detached_tasks_are_aborted() {
+        let runtime = runtime();
+        let (signal, exit) = ::signal();
+        let (tx, rx) = oneshot::channel::<()>();
+        drop(exit.abort_task_on_exit(runtime.spawn(hold(tx))));
+
+        signal.fire().unwrap();
+        // the task, holding the sender, is dropped once aborted.
+        assert!(runtime.block_on(rx).is_err());
+    }
+
+    // never finishes, holding on to `tx`.
+    fn hold(tx: oneshot::Sender<()>) -> impl future::Future<Output = ()> {
+        future::poll_fn(move |_| {
+            let _ = &tx;
+            ::std::task::Poll::Pending
+        })
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(feature = "timer")]
+use std::time::{Duration, Instant};
+
+use {Exit, FireError, Signal};
+
+/// Controller for a two-stage shutdown.
+///
+/// The soft exit asks work to stop accepting new jobs, while the hard exit
+/// demands immediate cancellation. Firing the hard stage also fires the soft
+/// one, and dropping the controller fires both.
+pub struct TwoStage {
+    hard: Signal,
+    soft: Signal,
+}
+
+impl TwoStage {
+    /// Get an exit that resolves once the soft stage fires.
+    pub fn soft_exit(&self) -> Exit {
+        self.soft.make_exit()
+    }
+
+    /// Get an exit that resolves once the hard stage fires.
+    pub fn hard_exit(&self) -> Exit {
+        self.hard.make_exit()
+    }
+
+    /// Fire the soft stage only.
+    pub fn fire_soft(&self) -> Result<usize, FireError> {
+        self.soft.fire()
+    }
+
+    /// Fire the hard stage, along with the soft stage if it hasn't already.
+    ///
+    /// The result only counts listeners on the hard exit.
+    pub fn fire_hard(&self) -> Result<usize, FireError> {
+        self.hard.fire()
+    }
+
+    /// Fire the soft stage now and the hard stage once `delay` has elapsed.
+    ///
+    /// A delay too long to represent never fires the hard stage.
+    #[cfg(feature = "timer")]
+    pub fn shutdown(self, delay: Duration) {
+        let _ = self.fire_soft();
+
+        match Instant::now().checked_add(delay) {
+            // the hard stage fires when the controller is dropped.
+            Some(at) => { let _ = ::timer::schedule(at, move || drop(self)); }
+            None => self.hard.defuse(),
+        }
+    }
+}
+
+/// Create a two-stage shutdown controller.
+pub fn two_stage() -> TwoStage {
+    let hard = ::signal_only();
+    let soft = hard.child();
+
+    TwoStage { hard, soft }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_then_hard() {
+        let stages = two_stage();
+        let (soft, hard) = (stages.soft_exit(), stages.hard_exit());
+
+        stages.fire_soft().unwrap();
+        assert!(!soft.is_live());
+        assert!(hard.is_live());
+
+        stages.fire_hard().unwrap();
+        assert!(!hard.is_live());
+    }
+
+    #[test]
+    fn hard_fires_soft() {
+        let stages = two_stage();
+        let soft = stages.soft_exit();
+
+        assert_eq!(stages.fire_hard(), Err(FireError::NoListeners));
+        assert!(!soft.is_live());
+    }
+
+    #[test]
+    fn drop_fires_both() {
+        let stages = two_stage();
+        let (soft, hard) = (stages.soft_exit(), stages.hard_exit());
+
+        drop(stages);
+        assert!(!soft.is_live() && !hard.is_live());
+    }
+
+    #[test]
+    #[cfg(all(feature = "timer", feature = "blocking"))]
+    fn delayed_hard_stage() {
+        let stages = two_stage();
+        let (soft, hard) = (stages.soft_exit(), stages.hard_exit());
+
+        stages.shutdown(Duration::from_millis(20));
+        assert!(!soft.is_live());
+        assert!(hard.is_live());
+        assert!(hard.wait_timeout(Duration::from_millis(5000)));
+    }
+
+    #[test]
+    #[cfg(feature = "timer")]
+    fn unrepresentable_delay() {
+        let stages = two_stage();
+        let (soft, hard) = (stages.soft_exit(), stages.hard_exit());
+
+        stages.shutdown(Duration::from_secs(u64::MAX));
+        assert!(!soft.is_live());
+        assert!(hard.is_live());
+    }
+}
@@ -0,0 +1,105 @@
+//! Sharing one shutdown source with the `stop-token` crate, used by
+//! async-std-centric libraries.
+
+use stop_token::{StopSource, StopToken};
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use compat03::Compat03;
+use {generation, Exit, FireHook, Shared, Signal};
+
+// fires once the token's source is dropped.
+impl From<StopToken> for Exit {
+    fn from(token: StopToken) -> Exit {
+        Exit::from_future(Compat03(token))
+    }
+}
+
+// drops the source on the first fire, completing its tokens.
+fn stop_on_fire<T: Send + 'static>(shared: &Arc<Shared<T>>, source: StopSource) -> FireHook {
+    let mut source = Some(source);
+    Shared::hook(shared, Box::new(move |_| {
+        drop(source.take());
+        false
+    }))
+}
+
+impl<T: Send + 'static> Exit<T> {
+    /// Get a stop token which completes once the signal fires.
+    ///
+    /// Only the first fire is observed: the token stays complete after a
+    /// reset. Tokens are shared until then, so calling this repeatedly
+    /// doesn't pile up sources.
+    pub fn to_stop_token(&self) -> StopToken {
+        let mut cached = self.shared.stop_token.lock();
+        let generation = generation(self.shared.state.load(Ordering::Acquire));
+        if let Some((cached_generation, ref token)) = *cached {
+            if cached_generation == generation {
+                return token.clone()
+            }
+        }
+
+        let source = StopSource::new();
+        let token = source.token();
+        // the hook goes once it has run, at most one per generation.
+        stop_on_fire(&self.shared, source).forget();
+        *cached = Some((generation, token.clone()));
+
+        token
+    }
+}
+
+impl<T: Send + 'static> Signal<T> {
+    /// Drop `source` once this signal fires, or right away if it already
+    /// has, so its tokens complete along with the exits.
+    ///
+    /// The source is dropped along with the returned hook if that comes
+    /// first.
+    pub fn stop_on_fire(&self, source: StopSource) -> FireHook {
+        stop_on_fire(&self.shared, source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "blocking")]
+    use std::time::Duration;
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn token_completes_on_fire() {
+        let (signal, exit) = ::signal();
+        let token = exit.to_stop_token();
+        let source = StopSource::new();
+        let other = source.token();
+        let _hook = signal.stop_on_fire(source);
+        assert!(!Exit::from(token.clone()).wait_timeout(Duration::from_millis(10)));
+
+        signal.fire().unwrap();
+        assert!(Exit::from(token).wait_timeout(Duration::from_millis(5000)));
+        assert!(Exit::from(other).wait_timeout(Duration::from_millis(5000)));
+    }
+
+    #[test]
+    fn tokens_are_shared_until_fired() {
+        let (signal, exit) = ::signal();
+        let _tokens: Vec<_> = (0..4).map(|_| exit.to_stop_token()).collect();
+        assert_eq!(exit.shared.waiting.lock().hooks.len(), 1);
+
+        drop(signal.stop_on_fire(StopSource::new()));
+        assert_eq!(exit.shared.waiting.lock().hooks.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn exit_from_token() {
+        let source = StopSource::new();
+        let exit = Exit::from(source.token());
+        assert!(!exit.wait_timeout(Duration::from_millis(10)));
+
+        drop(source);
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+}
@@ -0,0 +1,159 @@
+use futures::prelude::*;
+
+use Exit;
+
+/// Stream that yields items from an inner stream until the exit signal fires.
+pub struct TakeUntilExit<S, T = ()> {
+    inner: S,
+    exit: Exit<T>,
+    done: bool,
+}
+
+impl<S, T> TakeUntilExit<S, T> {
+    pub(crate) fn new(inner: S, exit: Exit<T>) -> TakeUntilExit<S, T> {
+        TakeUntilExit { inner, exit, done: false }
+    }
+}
+
+impl<S: Stream, T: Clone + Default> Stream for TakeUntilExit<S, T> {
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, S::Error> {
+        if self.done {
+            return Ok(Async::Ready(None))
+        }
+
+        // check the exit first, so a stream that is always ready can't
+        // keep the exit from ending it.
+        if self.exit.check().is_ready() {
+            self.done = true;
+            return Ok(Async::Ready(None))
+        }
+
+        let item = try_ready!(self.inner.poll());
+        if item.is_none() {
+            self.done = true;
+        }
+
+        Ok(Async::Ready(item))
+    }
+}
+
+/// Future that resolves to the next item of a borrowed stream, or to `None`
+/// if the stream ends or the exit signal fires first. Created by
+/// `ExitStreamExt::recv_until_exit`.
+pub struct RecvUntilExit<'a, S: 'a, T = ()> {
+    inner: &'a mut S,
+    exit: Exit<T>,
+}
+
+impl<'a, S: Stream, T: Clone + Default> Future for RecvUntilExit<'a, S, T> {
+    type Item = Option<S::Item>;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, S::Error> {
+        // like `TakeUntilExit`, a ready stream can't hold off the exit.
+        if self.exit.check().is_ready() {
+            return Ok(Async::Ready(None))
+        }
+
+        self.inner.poll()
+    }
+}
+
+/// Extension trait to consume any stream until an exit fires.
+pub trait ExitStreamExt: Stream + Sized {
+    /// Yield items from this stream until `exit` fires, then end.
+    fn take_until_exit<T>(self, exit: &Exit<T>) -> TakeUntilExit<Self, T> {
+        TakeUntilExit::new(self, exit.clone())
+    }
+
+    /// Receive the next item from this stream, such as an `mpsc::Receiver`,
+    /// resolving to `None` if it ends or `exit` fires first.
+    fn recv_until_exit<T>(&mut self, exit: &Exit<T>) -> RecvUntilExit<'_, Self, T> {
+        RecvUntilExit { inner: self, exit: exit.clone() }
+    }
+}
+
+impl<S: Stream> ExitStreamExt for S {}
+
+#[cfg(test)]
+mod tests {
+    use futures::{future, stream};
+    use futures::sync::mpsc;
+    use super::*;
+
+    #[test]
+    fn ends_when_stream_ends() {
+        let (_signal, exit) = ::signal();
+        let items = stream::iter_ok::<_, ()>(vec![1, 2, 3]).take_until_exit(&exit);
+
+        assert_eq!(items.collect().wait(), Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn ends_when_exit_fires() {
+        let (signal, exit) = ::signal();
+        let (tx, rx) = mpsc::unbounded::<u32>();
+        let mut items = rx.take_until_exit(&exit);
+
+        tx.unbounded_send(1).unwrap();
+        future::lazy(move || {
+            assert_eq!(items.poll(), Ok(Async::Ready(Some(1))));
+            assert_eq!(items.poll(), Ok(Async::NotReady));
+
+            signal.fire().unwrap();
+            tx.unbounded_send(2).unwrap();
+            assert_eq!(items.poll(), Ok(Async::Ready(None)));
+            assert_eq!(items.poll(), Ok(Async::Ready(None)));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn recv_until_exit() {
+        let (signal, exit) = ::signal();
+        let (tx, mut rx) = mpsc::channel::<u32>(1);
+
+        tx.send(1).wait().unwrap();
+        assert_eq!(rx.recv_until_exit(&exit).wait(), Ok(Some(1)));
+
+        signal.fire().unwrap();
+        assert_eq!(rx.recv_until_exit(&exit).wait(), Ok(None));
+    }
+
+    #[test]
+    fn until_stream() {
+        let (signal, exit) = ::signal();
+        let items = exit.clone().until_stream(stream::iter_ok::<_, ()>(vec![1, 2]));
+        assert_eq!(items.collect().wait(), Ok(vec![1, 2]));
+
+        signal.fire().unwrap();
+        let items = exit.until_stream(stream::iter_ok::<_, ()>(vec![1, 2]));
+        assert_eq!(items.collect().wait(), Ok(vec![]));
+    }
+
+    #[test]
+    fn exit_into_stream() {
+        let (signal, exit) = ::signal_with::<u32>();
+        let mut events = exit.into_stream();
+
+        future::lazy(move || {
+            assert_eq!(events.poll(), Ok(Async::NotReady));
+            signal.fire_with(3).unwrap();
+            assert_eq!(events.poll(), Ok(Async::Ready(Some(3))));
+            assert_eq!(events.poll(), Ok(Async::Ready(None)));
+            future::ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+
+    #[test]
+    fn always_ready_stream_ends() {
+        let (signal, exit) = ::signal();
+        signal.fire().unwrap();
+
+        let items = stream::repeat::<_, ()>(1).take_until_exit(&exit);
+        assert_eq!(items.collect().wait(), Ok(vec![]));
+    }
+}
@@ -0,0 +1,131 @@
+use std::thread::{Scope, ScopedJoinHandle};
+use std::time::{Duration, Instant};
+
+use Exit;
+
+/// Blocking view of an exit for worker threads that don't run futures.
+/// Created by `Exit::into_sync`.
+///
+/// Waits park the thread on the signal's condvar, so no executor is needed.
+/// Like the exit it wraps, it keeps the signal's listener count up.
+#[derive(Clone)]
+pub struct SyncExit<T = ()> {
+    exit: Exit<T>,
+}
+
+impl<T> SyncExit<T> {
+    /// Whether the signal has fired.
+    pub fn is_cancelled(&self) -> bool {
+        !self.exit.is_live()
+    }
+
+    /// Block until the signal fires or `timeout` has elapsed, returning
+    /// whether it fired.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.exit.wait_timeout(timeout)
+    }
+
+    /// Block until the signal fires or `deadline` is reached, returning
+    /// whether it fired.
+    pub fn wait_deadline(&self, deadline: Instant) -> bool {
+        self.exit.wait_deadline(deadline)
+    }
+
+    /// Get back the exit, for use in a task.
+    pub fn into_exit(self) -> Exit<T> {
+        self.exit
+    }
+}
+
+impl<T: Clone + Default> SyncExit<T> {
+    /// Block until the signal fires, returning the reason.
+    pub fn wait(&self) -> T {
+        self.exit.shared.wait_until(|state| self.exit.has_fired(state), None);
+        self.exit.shared.reason()
+    }
+}
+
+impl<T> From<Exit<T>> for SyncExit<T> {
+    fn from(exit: Exit<T>) -> SyncExit<T> {
+        SyncExit { exit }
+    }
+}
+
+/// Scope for spawning threads that each get a `SyncExit`. Created by
+/// `Exit::scope`.
+pub struct ExitScope<'scope, 'env: 'scope, T = ()> {
+    scope: &'scope Scope<'scope, 'env>,
+    exit: Exit<T>,
+}
+
+impl<'scope, 'env, T> ExitScope<'scope, 'env, T> {
+    /// Get a blocking view of the exit, for the scope body to check.
+    pub fn exit(&self) -> SyncExit<T> {
+        self.exit.clone().into_sync()
+    }
+
+    /// Spawn a scoped thread, passing it a blocking view of the exit. It
+    /// should return once the exit fires, so the scope can end.
+    pub fn spawn<F, R>(&self, f: F) -> ScopedJoinHandle<'scope, R>
+        where F: FnOnce(SyncExit<T>) -> R + Send + 'scope, R: Send + 'scope, T: Send + Sync + 'scope
+    {
+        let exit = self.exit();
+        self.scope.spawn(move || f(exit))
+    }
+}
+
+impl<T> Exit<T> {
+    /// Run `f` in a `std::thread::scope` whose threads each get a blocking
+    /// view of this exit, returning once they have all been joined.
+    ///
+    /// Like the underlying scope, this only ends once every thread does, so
+    /// scoped threads should check their exit and return once it fires.
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+        where F: for<'scope> FnOnce(&ExitScope<'scope, 'env, T>) -> R
+    {
+        ::std::thread::scope(|scope| f(&ExitScope { scope, exit: self.clone() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn waits_for_fire() {
+        let (signal, exit) = ::signal_with::<u32>();
+        let exit = exit.into_sync();
+        assert!(!exit.is_cancelled());
+        assert!(!exit.wait_timeout(Duration::from_millis(20)));
+
+        let worker = exit.clone();
+        let worker = thread::spawn(move || worker.wait());
+        signal.fire_with(3).unwrap();
+
+        assert_eq!(worker.join().unwrap(), 3);
+        assert!(exit.is_cancelled());
+        assert!(exit.wait_deadline(Instant::now()));
+    }
+
+    #[test]
+    fn scoped_threads_end_on_fire() {
+        let (signal, exit) = ::signal();
+        let mut counts = [0, 0];
+
+        exit.scope(|s| {
+            for count in &mut counts {
+                s.spawn(move |exit| {
+                    while !exit.wait_timeout(Duration::from_millis(1)) {
+                        *count += 1;
+                    }
+                });
+            }
+
+            assert!(!s.exit().is_cancelled());
+            signal.fire().unwrap();
+        });
+
+        assert!(exit.wait_timeout(Duration::from_millis(0)));
+    }
+}
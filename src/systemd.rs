@@ -0,0 +1,84 @@
+//! Service state notifications for systemd, sent to `$NOTIFY_SOCKET`.
+
+use std::env;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+
+use Signal;
+
+/// Send a state notification such as `READY=1`, returning `false` if the
+/// process wasn't started by systemd with a notification socket.
+pub fn notify(state: &str) -> io::Result<bool> {
+    let path = match env::var_os("NOTIFY_SOCKET") {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+
+    let socket = UnixDatagram::unbound()?;
+    let path = path.to_string_lossy();
+    if let Some(name) = path.strip_prefix('@') {
+        send_abstract(&socket, name, state)?;
+    } else {
+        socket.send_to(state.as_bytes(), &*path)?;
+    }
+
+    Ok(true)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn send_abstract(socket: &UnixDatagram, name: &str, state: &str) -> io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::SocketAddr;
+
+    let addr = SocketAddr::from_abstract_name(name)?;
+    socket.send_to_addr(state.as_bytes(), &addr).map(|_| ())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn send_abstract(_: &UnixDatagram, _: &str, _: &str) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "abstract sockets are only supported on linux"))
+}
+
+/// Tell systemd that startup has finished.
+pub fn notify_ready() -> io::Result<bool> {
+    notify("READY=1")
+}
+
+/// Tell systemd that the service is stopping once `signal` fires.
+pub fn notify_stopping_on_fire<T>(signal: &Signal<T>) {
+    // lives as long as the signal, which fires at the latest when dropped.
+    let _ = signal.shared.add_hook(Box::new(|_| {
+        let _ = notify("STOPPING=1");
+        false
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn notifies_socket() {
+        let path = env::temp_dir().join(format!("exit-future-notify-{}", ::std::process::id()));
+        let _ = ::std::fs::remove_file(&path);
+        let listener = UnixDatagram::bind(&path).unwrap();
+        listener.set_read_timeout(Some(Duration::from_millis(5000))).unwrap();
+        env::set_var("NOTIFY_SOCKET", &path);
+
+        let mut buf = [0; 64];
+        assert!(notify_ready().unwrap());
+        let n = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"READY=1");
+
+        let signal = ::signal_only();
+        notify_stopping_on_fire(&signal);
+        let _ = signal.fire();
+        let n = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"STOPPING=1");
+
+        env::remove_var("NOTIFY_SOCKET");
+        let _ = ::std::fs::remove_file(&path);
+        assert!(!notify("STATUS=done").unwrap());
+    }
+}
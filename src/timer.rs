@@ -0,0 +1,210 @@
+//! A minimal timer, driven by a single lazily-spawned background thread.
+
+use parking_lot::{Condvar, Mutex};
+use futures::prelude::*;
+use futures::task::AtomicTask;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Instant;
+
+type Callback = Box<dyn FnOnce() + Send>;
+
+// ordered by deadline, with a sequence number so entries with equal
+// deadlines run in insertion order.
+type Key = (Instant, u64);
+
+struct Queue {
+    entries: BTreeMap<Key, Callback>,
+    seq: u64,
+}
+
+struct Timer {
+    queue: Mutex<Queue>,
+    wake: Condvar,
+}
+
+impl Timer {
+    fn run(&self) {
+        let mut queue = self.queue.lock();
+        loop {
+            let now = Instant::now();
+            let mut due = Vec::new();
+            while let Some(entry) = queue.entries.first_entry() {
+                if entry.key().0 > now {
+                    break
+                }
+                due.push(entry.remove());
+            }
+
+            if !due.is_empty() {
+                // run callbacks without holding the lock, so they may
+                // schedule more work.
+                drop(queue);
+                for callback in due {
+                    callback();
+                }
+                queue = self.queue.lock();
+                continue;
+            }
+
+            match queue.entries.keys().next().map(|&(at, _)| at) {
+                Some(at) => { let _ = self.wake.wait_until(&mut queue, at); }
+                None => self.wake.wait(&mut queue),
+            }
+        }
+    }
+}
+
+fn timer() -> &'static Timer {
+    static TIMER: OnceLock<Timer> = OnceLock::new();
+
+    TIMER.get_or_init(|| {
+        thread::Builder::new()
+            .name("exit-future-timer".into())
+            .spawn(|| timer().run())
+            .expect("failed to spawn timer thread");
+
+        Timer {
+            queue: Mutex::new(Queue { entries: BTreeMap::new(), seq: 0 }),
+            wake: Condvar::new(),
+        }
+    })
+}
+
+/// Run `callback` on the timer thread once `at` is reached, unless it's
+/// cancelled with the returned key first.
+pub(crate) fn schedule<F: FnOnce() + Send + 'static>(at: Instant, callback: F) -> TimerKey {
+    let timer = timer();
+    let mut queue = timer.queue.lock();
+
+    let key = (at, queue.seq);
+    queue.seq += 1;
+
+    let earliest = queue.entries.keys().next().is_none_or(|&(first, _)| at < first);
+    let _ = queue.entries.insert(key, Box::new(callback));
+
+    if earliest {
+        timer.wake.notify_one();
+    }
+    TimerKey(key)
+}
+
+/// Identifies a callback passed to `schedule`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TimerKey(Key);
+
+/// Drop the callback scheduled under `key`, if it hasn't run yet.
+pub(crate) fn cancel(key: TimerKey) {
+    // dropped without holding the lock, in case it schedules more work.
+    let callback = timer().queue.lock().entries.remove(&key.0);
+    drop(callback);
+}
+
+struct DelayState {
+    elapsed: AtomicBool,
+    task: AtomicTask,
+}
+
+/// Future that resolves once a deadline is reached.
+///
+/// Dropping it takes it off the timer.
+pub(crate) struct Delay {
+    state: Arc<DelayState>,
+    key: TimerKey,
+}
+
+impl Delay {
+    pub(crate) fn new(at: Instant) -> Delay {
+        let state = Arc::new(DelayState {
+            elapsed: AtomicBool::new(false),
+            task: AtomicTask::new(),
+        });
+
+        let timer_state = state.clone();
+        let key = schedule(at, move || {
+            timer_state.elapsed.store(true, Ordering::Release);
+            timer_state.task.notify();
+        });
+
+        Delay { state, key }
+    }
+}
+
+impl Drop for Delay {
+    fn drop(&mut self) {
+        if !self.state.elapsed.load(Ordering::Acquire) {
+            cancel(self.key);
+        }
+    }
+}
+
+impl Future for Delay {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        self.state.task.register();
+
+        if self.state.elapsed.load(Ordering::Acquire) {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn delay_elapses() {
+        let start = Instant::now();
+        Delay::new(start + Duration::from_millis(50)).wait().unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn callbacks_run_in_deadline_order() {
+        let (tx, rx) = mpsc::channel();
+        let now = Instant::now();
+
+        for &(ms, id) in &[(60, 2), (20, 0), (40, 1)] {
+            let tx = tx.clone();
+            schedule(now + Duration::from_millis(ms), move || tx.send(id).unwrap());
+        }
+
+        let order: Vec<u32> = rx.iter().take(3).collect();
+        assert_eq!(order, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn dropped_delays_leave_the_queue() {
+        let at = Instant::now() + Duration::from_secs(3600);
+        let delay = Delay::new(at);
+        let key = delay.key.0;
+        assert!(timer().queue.lock().entries.contains_key(&key));
+
+        drop(delay);
+        assert!(!timer().queue.lock().entries.contains_key(&key));
+    }
+
+    #[test]
+    fn cancelled_callbacks_do_not_run() {
+        let (tx, rx) = mpsc::channel();
+        let now = Instant::now();
+
+        let cancelled = tx.clone();
+        let key = schedule(now + Duration::from_millis(20), move || cancelled.send(0).unwrap());
+        schedule(now + Duration::from_millis(40), move || tx.send(1).unwrap());
+        cancel(key);
+
+        assert_eq!(rx.iter().collect::<Vec<u32>>(), vec![1]);
+    }
+}
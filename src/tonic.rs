@@ -0,0 +1,216 @@
+//! Draining tonic gRPC servers. `GracefulShutdown::signal` is the future
+//! for `serve_with_shutdown`, on which tonic sends GOAWAY and stops
+//! accepting connections, and `GracefulShutdown::grpc_layer` goes to
+//! `Server::layer`, rejecting RPCs that still arrive on open connections
+//! while tracking the active ones, so `GracefulShutdown::drain` waits for
+//! them.
+
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use hyper::GracefulShutdown;
+use tower::{Shutdown, ShutdownLayer};
+
+/// Error type that tonic's `Server::layer` accepts from services.
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Error rejecting RPCs that arrive once shutdown has started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuttingDown;
+
+impl fmt::Display for ShuttingDown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("server is shutting down")
+    }
+}
+
+impl Error for ShuttingDown {}
+
+fn shutting_down() -> BoxError {
+    Box::new(ShuttingDown)
+}
+
+impl<T> GracefulShutdown<T> {
+    /// Get a layer for tonic's `Server::layer` which rejects RPCs with
+    /// `ShuttingDown` once the exit fires, and counts the active ones
+    /// along with the open connections, so `drain` waits for them.
+    pub fn grpc_layer(&self) -> GrpcLayer<T> {
+        GrpcLayer {
+            inner: ShutdownLayer::new(&self.exit, shutting_down as fn() -> BoxError).track(&self.tracker),
+        }
+    }
+}
+
+/// Layer for tonic servers, created by `GracefulShutdown::grpc_layer`.
+///
+/// tonic's routes never fail, so their errors are boxed to make room for
+/// the rejection.
+#[derive(Clone)]
+pub struct GrpcLayer<T = ()> {
+    inner: ShutdownLayer<fn() -> BoxError, T>,
+}
+
+impl<S, T> Layer<S> for GrpcLayer<T> {
+    type Service = Shutdown<BoxErrors<S>, fn() -> BoxError, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        self.inner.layer(BoxErrors { inner })
+    }
+}
+
+/// Service boxing the errors of the one it wraps. Created by `GrpcLayer`.
+#[derive(Clone)]
+pub struct BoxErrors<S> {
+    inner: S,
+}
+
+impl<S, Request> Service<Request> for BoxErrors<S>
+    where S: Service<Request>, S::Error: Into<BoxError>
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = BoxErrorsFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        BoxErrorsFuture { inner: self.inner.call(request) }
+    }
+}
+
+pin_project! {
+    /// Response future of `BoxErrors`.
+    pub struct BoxErrorsFuture<F> {
+        #[pin]
+        inner: F,
+    }
+}
+
+impl<F, R, E: Into<BoxError>> Future for BoxErrorsFuture<F>
+    where F: Future<Output = Result<R, E>>
+{
+    type Output = Result<R, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<R, BoxError>> {
+        self.project().inner.poll(cx).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hyper;
+    extern crate tonic;
+
+    use self::hyper::{Request, Response};
+    use self::tonic::body::Body;
+    use self::tonic::server::NamedService;
+    use self::tonic::transport::server::TcpIncoming;
+    use self::tonic::transport::Server;
+    use std::convert::Infallible;
+    use std::future::{self, Ready};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::task::Waker;
+    use std::thread;
+    use std::time::Duration;
+    use super::*;
+
+    struct Rpc;
+
+    impl Service<()> for Rpc {
+        type Response = ();
+        type Error = BoxError;
+        type Future = Ready<Result<(), BoxError>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: ()) -> Self::Future {
+            future::ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn drains_active_rpcs() {
+        let (signal, exit) = ::signal();
+        let graceful = GracefulShutdown::new(&exit);
+        let mut service = graceful.grpc_layer().layer(Rpc);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let mut active = Box::pin(service.call(()));
+        signal.fire().unwrap();
+        let mut rejected = Box::pin(service.call(()));
+        match rejected.as_mut().poll(&mut cx) {
+            Poll::Ready(Err(e)) => assert!(e.is::<ShuttingDown>()),
+            _ => panic!("RPC accepted after shutdown"),
+        }
+
+        let mut drain = Box::pin(graceful.drain(Duration::from_secs(3600)));
+        assert_eq!(drain.as_mut().poll(&mut cx), Poll::Pending);
+        assert!(matches!(active.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+        drop(active);
+        assert_eq!(drain.as_mut().poll(&mut cx), Poll::Ready(true));
+    }
+
+    #[derive(Clone)]
+    struct Health;
+
+    impl NamedService for Health {
+        const NAME: &'static str = "test.Health";
+    }
+
+    impl Service<Request<Body>> for Health {
+        type Response = Response<Body>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<Body>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Request<Body>) -> Self::Future {
+            future::ready(Ok(Response::new(Body::empty())))
+        }
+    }
+
+    #[test]
+    fn shuts_down_tonic_servers() {
+        let (signal, exit) = ::signal();
+        let graceful = GracefulShutdown::new(&exit);
+        let runtime = ::tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let incoming = {
+            let _entered = runtime.enter();
+            TcpIncoming::bind("127.0.0.1:0".parse().unwrap()).unwrap()
+        };
+        let addr = incoming.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"POST /test.Health/Check HTTP/1.1\r\nHost: test\r\nContent-Length: 0\r\n\r\n").unwrap();
+
+            let mut response = [0; 12];
+            stream.read_exact(&mut response).unwrap();
+            assert_eq!(&response, b"HTTP/1.1 200");
+            signal.fire().unwrap();
+        });
+
+        let server = Server::builder()
+            .accept_http1(true)
+            .layer(graceful.grpc_layer())
+            .add_service(Health)
+            .serve_with_incoming_shutdown(incoming, graceful.signal());
+        assert!(runtime.block_on(server).is_ok());
+        assert!(runtime.block_on(graceful.drain(Duration::from_millis(5000))));
+        client.join().unwrap();
+    }
+}
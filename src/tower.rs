@@ -0,0 +1,165 @@
+//! A tower layer that stops taking requests once the exit fires, letting
+//! the ones already in flight finish.
+
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use {Exit, Tracker, TrackerToken};
+
+/// Layer wrapping services in `Shutdown`.
+#[derive(Clone)]
+pub struct ShutdownLayer<F, T = ()> {
+    exit: Exit<T>,
+    reject: F,
+    tracker: Option<Tracker>,
+}
+
+impl<F, T> ShutdownLayer<F, T> {
+    /// Reject requests made once `exit` has fired with the error returned
+    /// by `reject`.
+    pub fn new(exit: &Exit<T>, reject: F) -> ShutdownLayer<F, T> {
+        ShutdownLayer { exit: exit.clone(), reject, tracker: None }
+    }
+
+    /// Track the requests in flight with `tracker`, so shutdown can wait
+    /// for them to drain.
+    pub fn track(mut self, tracker: &Tracker) -> ShutdownLayer<F, T> {
+        self.tracker = Some(tracker.clone());
+        self
+    }
+}
+
+impl<S, F: Clone, T> Layer<S> for ShutdownLayer<F, T> {
+    type Service = Shutdown<S, F, T>;
+
+    fn layer(&self, inner: S) -> Shutdown<S, F, T> {
+        Shutdown {
+            inner,
+            exit: self.exit.clone(),
+            reject: self.reject.clone(),
+            tracker: self.tracker.clone(),
+        }
+    }
+}
+
+/// Service that rejects requests once the exit signal fires, while the
+/// ones already accepted run to completion. Created by `ShutdownLayer`.
+#[derive(Clone)]
+pub struct Shutdown<S, F, T = ()> {
+    inner: S,
+    exit: Exit<T>,
+    reject: F,
+    tracker: Option<Tracker>,
+}
+
+impl<S, F, T, Request> Service<Request> for Shutdown<S, F, T>
+    where S: Service<Request>, F: Fn() -> S::Error
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        // once fired, be ready so the caller gets the rejection right away.
+        if !self.exit.is_live() {
+            return Poll::Ready(Ok(()))
+        }
+
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        if !self.exit.is_live() {
+            return ResponseFuture::Rejected { error: Some((self.reject)()) }
+        }
+
+        let token = self.tracker.as_ref().map(Tracker::token);
+        ResponseFuture::Accepted { inner: self.inner.call(request), _token: token }
+    }
+}
+
+pin_project! {
+    /// Response future of `Shutdown`, holding up the tracker, if any,
+    /// until it completes or is dropped.
+    #[project = ResponseProj]
+    pub enum ResponseFuture<F, E> {
+        #[doc(hidden)]
+        Accepted {
+            #[pin]
+            inner: F,
+            _token: Option<TrackerToken>,
+        },
+        #[doc(hidden)]
+        Rejected {
+            error: Option<E>,
+        },
+    }
+}
+
+impl<F, R, E> Future for ResponseFuture<F, E>
+    where F: Future<Output = Result<R, E>>
+{
+    type Output = Result<R, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<R, E>> {
+        match self.project() {
+            ResponseProj::Accepted { inner, .. } => inner.poll(cx),
+            ResponseProj::Rejected { error } => {
+                Poll::Ready(Err(error.take().expect("cannot poll ResponseFuture twice")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::{self, Ready};
+    use std::task::Waker;
+    use super::*;
+
+    struct Echo;
+
+    impl Service<u32> for Echo {
+        type Response = u32;
+        type Error = &'static str;
+        type Future = Ready<Result<u32, &'static str>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), &'static str>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: u32) -> Self::Future {
+            future::ready(Ok(request))
+        }
+    }
+
+    fn poll<F: Future>(f: Pin<&mut F>) -> Poll<F::Output> {
+        f.poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn rejects_once_fired() {
+        let (signal, exit) = ::signal();
+        let tracker = Tracker::new();
+        let mut service = ShutdownLayer::new(&exit, || "shutting down").track(&tracker).layer(Echo);
+
+        let mut accepted = Box::pin(service.call(1));
+        assert_eq!(tracker.len(), 1);
+
+        signal.fire().unwrap();
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(service.poll_ready(&mut cx), Poll::Ready(Ok(())));
+        let mut rejected = Box::pin(service.call(2));
+        assert_eq!(poll(rejected.as_mut()), Poll::Ready(Err("shutting down")));
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(poll(accepted.as_mut()), Poll::Ready(Ok(1)));
+
+        drop(accepted);
+        assert!(tracker.is_empty());
+    }
+}
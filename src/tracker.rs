@@ -0,0 +1,266 @@
+#[cfg(feature = "blocking")]
+use parking_lot::Condvar;
+use futures::prelude::*;
+use futures::task::AtomicTask;
+
+use lock::Mutex;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::vec::Vec;
+#[cfg(feature = "blocking")]
+use std::time::{Duration, Instant};
+
+struct Inner {
+    count: AtomicUsize,
+    next_id: AtomicUsize,
+    waiting: Mutex<BTreeMap<usize, Arc<AtomicTask>>>,
+    // notified when drained, for blocking waits.
+    #[cfg(feature = "blocking")]
+    drained: Condvar,
+}
+
+impl Inner {
+    fn is_empty(&self) -> bool {
+        self.count.load(Ordering::Acquire) == 0
+    }
+
+    fn release(&self) {
+        if self.count.fetch_sub(1, Ordering::AcqRel) != 1 {
+            return
+        }
+
+        let wake_up = {
+            let waiting = self.waiting.lock();
+            #[cfg(feature = "blocking")]
+            self.drained.notify_all();
+            waiting.values().cloned().collect::<Vec<_>>()
+        };
+
+        for task in wake_up {
+            task.notify()
+        }
+    }
+}
+
+/// Tracks in-flight work, so that a shutdown sequence can fire its signal
+/// and then wait for all of the work to finish.
+///
+/// Clones track the same work.
+#[derive(Clone)]
+pub struct Tracker {
+    inner: Arc<Inner>,
+}
+
+impl Tracker {
+    /// Create a tracker with no work in flight.
+    pub fn new() -> Tracker {
+        Tracker {
+            inner: Arc::new(Inner {
+                count: AtomicUsize::new(0),
+                next_id: AtomicUsize::new(0),
+                waiting: Mutex::new(BTreeMap::new()),
+                #[cfg(feature = "blocking")]
+                drained: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Wrap work so that it's tracked until it completes or is dropped.
+    pub fn track<F: IntoFuture>(&self, f: F) -> Tracked<F::Future> {
+        Tracked { inner: f.into_future(), token: Some(self.token()) }
+    }
+
+    /// Get a token that counts as in-flight work until it's dropped, for
+    /// work that isn't a future.
+    pub fn token(&self) -> TrackerToken {
+        self.inner.count.fetch_add(1, Ordering::AcqRel);
+        TrackerToken { inner: self.inner.clone() }
+    }
+
+    /// The amount of work in flight.
+    pub fn len(&self) -> usize {
+        self.inner.count.load(Ordering::Acquire)
+    }
+
+    /// Whether there is no work in flight.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Get a future which resolves once there is no work in flight.
+    pub fn wait(&self) -> TrackerWait {
+        TrackerWait { inner: self.inner.clone(), slot: None }
+    }
+
+    /// Block the current thread until there is no work in flight or
+    /// `timeout` has elapsed, returning whether the work drained.
+    #[cfg(feature = "blocking")]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut waiting = self.inner.waiting.lock();
+
+        while !self.inner.is_empty() {
+            match deadline {
+                Some(deadline) => if self.inner.drained.wait_until(&mut waiting, deadline).timed_out() {
+                    break
+                },
+                None => self.inner.drained.wait(&mut waiting),
+            }
+        }
+
+        self.inner.is_empty()
+    }
+}
+
+impl Default for Tracker {
+    fn default() -> Tracker {
+        Tracker::new()
+    }
+}
+
+/// Token counting as in-flight work for a `Tracker` until dropped.
+pub struct TrackerToken {
+    inner: Arc<Inner>,
+}
+
+impl Drop for TrackerToken {
+    fn drop(&mut self) {
+        self.inner.release();
+    }
+}
+
+/// Future tracked by a `Tracker` until it completes or is dropped.
+pub struct Tracked<F> {
+    inner: F,
+    // released as soon as the work completes, even if the future is kept.
+    token: Option<TrackerToken>,
+}
+
+impl<F: Future> Future for Tracked<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Poll<F::Item, F::Error> {
+        let res = self.inner.poll();
+        if let Ok(Async::NotReady) = res {
+            return res
+        }
+
+        self.token = None;
+        res
+    }
+}
+
+/// Future that resolves once a `Tracker` has no work in flight.
+pub struct TrackerWait {
+    inner: Arc<Inner>,
+    slot: Option<(usize, Arc<AtomicTask>)>,
+}
+
+impl Future for TrackerWait {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        if self.inner.is_empty() {
+            return Ok(Async::Ready(()))
+        }
+
+        let inner = &self.inner;
+        let &mut (_, ref task) = self.slot.get_or_insert_with(|| {
+            let task = Arc::new(AtomicTask::new());
+            let id = inner.next_id.fetch_add(1, Ordering::Relaxed);
+            inner.waiting.lock().insert(id, task.clone());
+
+            (id, task)
+        });
+
+        // update the slot before checking, so a concurrent release can't
+        // go unnoticed.
+        task.register();
+
+        if inner.is_empty() {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+impl Drop for TrackerWait {
+    fn drop(&mut self) {
+        if let Some((id, _)) = self.slot {
+            self.inner.waiting.lock().remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::sync::oneshot;
+    use std::thread;
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn waits_for_tracked_work() {
+        let tracker = Tracker::new();
+        let (tx, rx) = oneshot::channel::<()>();
+        let work = tracker.track(rx);
+        assert_eq!(tracker.len(), 1);
+
+        let handle = thread::spawn(move || work.wait());
+        thread::sleep(Duration::from_millis(20));
+        assert!(!tracker.is_empty());
+        tx.send(()).unwrap();
+
+        tracker.wait().wait().unwrap();
+        assert!(tracker.is_empty());
+        assert!(handle.join().unwrap().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn dropping_work_releases_it() {
+        let tracker = Tracker::new();
+        let work = tracker.track(::futures::future::empty::<(), ()>());
+        let token = tracker.clone().token();
+        assert_eq!(tracker.len(), 2);
+
+        drop(work);
+        assert!(!tracker.wait_timeout(Duration::from_millis(10)));
+        drop(token);
+        assert!(tracker.wait_timeout(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn completing_work_releases_it() {
+        let tracker = Tracker::new();
+        let mut ok = tracker.track(Ok::<_, ()>(1));
+        let mut err = tracker.track(Err::<(), _>(2));
+        assert_eq!(tracker.len(), 2);
+
+        assert_eq!(ok.poll(), Ok(Async::Ready(1)));
+        assert_eq!(err.poll(), Err(2));
+        // the futures are still around, but no longer count.
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn signal_then_drain() {
+        let (signal, exit) = ::signal();
+        let tracker = Tracker::new();
+
+        let handles: Vec<_> = (0..4).map(|_| {
+            let work = tracker.track(exit.clone());
+            thread::spawn(move || work.wait())
+        }).collect();
+
+        signal.fire().unwrap();
+        tracker.wait().wait().unwrap();
+        for handle in handles {
+            assert!(handle.join().unwrap().is_ok());
+        }
+    }
+}
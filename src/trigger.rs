@@ -0,0 +1,144 @@
+//! Conversions with the `triggered` crate's trigger and listener, so the two
+//! can share one shutdown without bridging tasks.
+
+use triggered::{self, Listener, Trigger};
+
+use compat03::Compat03;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use {generation, Exit, FireHook, Shared, Signal};
+
+// fires once triggered.
+impl From<Listener> for Exit {
+    fn from(listener: Listener) -> Exit {
+        Exit::from_future(Compat03(listener))
+    }
+}
+
+// triggers once the signal fires.
+impl<T: Send + 'static> From<Exit<T>> for Listener {
+    fn from(exit: Exit<T>) -> Listener {
+        exit.to_listener()
+    }
+}
+
+// fires the signal once triggered, observed through its exits.
+impl From<Signal> for Trigger {
+    fn from(signal: Signal) -> Trigger {
+        let (trigger, listener) = triggered::trigger();
+        signal.shared.add_driver(Box::new(Compat03(listener)));
+        signal.defuse();
+
+        trigger
+    }
+}
+
+// triggers when the new signal fires.
+impl<T: Send + 'static> From<Trigger> for Signal<T> {
+    fn from(trigger: Trigger) -> Signal<T> {
+        let signal = ::signal_only_with();
+        signal.trigger_on_fire(trigger).forget();
+
+        signal
+    }
+}
+
+fn trigger_on_fire<T: Send + 'static>(shared: &Arc<Shared<T>>, trigger: Trigger) -> FireHook {
+    Shared::hook(shared, Box::new(move |_| {
+        trigger.trigger();
+        false
+    }))
+}
+
+impl<T: Send + 'static> Exit<T> {
+    /// Get a listener which is triggered once the signal fires.
+    ///
+    /// Listeners are shared until the signal fires, so calling this
+    /// repeatedly doesn't pile up triggers.
+    pub fn to_listener(&self) -> Listener {
+        let mut cached = self.shared.listener.lock();
+        let generation = generation(self.shared.state.load(Ordering::Acquire));
+        if let Some((cached_generation, ref listener)) = *cached {
+            if cached_generation == generation {
+                return listener.clone()
+            }
+        }
+
+        let (trigger, listener) = triggered::trigger();
+        // the hook goes once it has run, at most one per generation.
+        trigger_on_fire(&self.shared, trigger).forget();
+        *cached = Some((generation, listener.clone()));
+
+        listener
+    }
+}
+
+impl<T: Send + 'static> Signal<T> {
+    /// Trigger `trigger` once this signal fires, or right away if it
+    /// already has, unless the returned hook is dropped first.
+    pub fn trigger_on_fire(&self, trigger: Trigger) -> FireHook {
+        trigger_on_fire(&self.shared, trigger)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "blocking")]
+    use std::time::Duration;
+    use super::*;
+
+    #[test]
+    fn listener_from_exit() {
+        let (signal, exit) = ::signal();
+        let listener = Listener::from(exit.clone());
+        assert!(!listener.is_triggered());
+
+        signal.fire().unwrap();
+        assert!(listener.is_triggered());
+    }
+
+    #[test]
+    fn listeners_are_shared_until_fired() {
+        let (signal, exit) = ::signal();
+        let first = exit.to_listener();
+        let _second = exit.to_listener();
+        assert_eq!(exit.shared.waiting.lock().hooks.len(), 1);
+
+        let (trigger, other) = triggered::trigger();
+        drop(signal.trigger_on_fire(trigger));
+        assert_eq!(exit.shared.waiting.lock().hooks.len(), 1);
+
+        signal.fire().unwrap();
+        assert!(first.is_triggered());
+        assert!(!other.is_triggered());
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn exit_from_listener() {
+        let (trigger, listener) = triggered::trigger();
+        let exit = Exit::from(listener);
+        assert!(!exit.wait_timeout(Duration::from_millis(10)));
+
+        trigger.trigger();
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+
+    #[test]
+    #[cfg(feature = "blocking")]
+    fn signal_and_trigger() {
+        let (trigger, listener) = triggered::trigger();
+        let signal = Signal::<()>::from(trigger);
+        let exit = signal.make_exit();
+        signal.fire().unwrap();
+        assert!(listener.is_triggered());
+        assert!(!exit.is_live());
+
+        let (signal, exit) = ::signal();
+        let trigger = Trigger::from(signal);
+        assert!(!exit.wait_timeout(Duration::from_millis(10)));
+        trigger.trigger();
+        assert!(exit.wait_timeout(Duration::from_millis(5000)));
+    }
+}
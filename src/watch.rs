@@ -0,0 +1,87 @@
+//! Bridging exits with tokio `watch` channels, for crates that shut down by
+//! sending `true` through a `watch::Sender<bool>`.
+
+use tokio::sync::watch;
+
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use {generation, Exit, FireHook, Shared, Signal};
+
+// sets the channel to `true` on the first fire.
+fn send_on_fire<T: Send + 'static>(shared: &Arc<Shared<T>>, tx: watch::Sender<bool>) -> FireHook {
+    Shared::hook(shared, Box::new(move |_| {
+        tx.send_replace(true);
+        false
+    }))
+}
+
+impl<T: Send + 'static> Exit<T> {
+    /// Turn the exit into a watch receiver, whose value changes from
+    /// `false` to `true` once the signal fires.
+    ///
+    /// Only the first fire is observed: the value stays `true` after a
+    /// reset. Receivers share a channel until then, so calling this
+    /// repeatedly doesn't pile up hooks.
+    pub fn into_watch(self) -> watch::Receiver<bool> {
+        let mut cached = self.shared.watch_receiver.lock();
+        let generation = generation(self.shared.state.load(Ordering::Acquire));
+        if let Some((cached_generation, ref rx)) = *cached {
+            if cached_generation == generation {
+                return rx.clone()
+            }
+        }
+
+        let (tx, rx) = watch::channel(false);
+        // the hook goes once it has run, at most one per generation.
+        send_on_fire(&self.shared, tx).forget();
+        *cached = Some((generation, rx.clone()));
+
+        rx
+    }
+}
+
+impl<T: Send + 'static> Signal<T> {
+    /// Create a signal which sets `tx` to `true` once it fires, so receivers
+    /// of the channel observe it along with the signal's exits.
+    pub fn from_watch_sender(tx: watch::Sender<bool>) -> Signal<T> {
+        let signal = ::signal_only_with();
+        send_on_fire(&signal.shared, tx).forget();
+
+        signal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::runtime::Builder;
+    use super::*;
+
+    #[test]
+    fn watch_from_exit() {
+        let (signal, exit) = ::signal();
+        let mut rx = exit.clone().into_watch();
+        let _other = exit.clone().into_watch();
+        assert_eq!(exit.shared.waiting.lock().hooks.len(), 1);
+        assert!(!*rx.borrow());
+
+        signal.fire().unwrap();
+        let runtime = Builder::new_current_thread().build().unwrap();
+        runtime.block_on(rx.changed()).unwrap();
+        assert!(*rx.borrow());
+
+        assert!(*exit.into_watch().borrow());
+    }
+
+    #[test]
+    fn signal_from_sender() {
+        let (tx, rx) = watch::channel(false);
+        let signal = Signal::<()>::from_watch_sender(tx);
+        let exit = signal.make_exit();
+        assert!(!*rx.borrow());
+
+        drop(signal);
+        assert!(!exit.is_live());
+        assert!(*rx.borrow());
+    }
+}
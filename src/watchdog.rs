@@ -0,0 +1,116 @@
+use parking_lot::Mutex;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use {Exit, Signal};
+
+struct State {
+    signal: Signal,
+    interval: Duration,
+    // `None` once the deadline is too far away to represent.
+    deadline: Mutex<Option<Instant>>,
+}
+
+impl State {
+    fn arm(state: &Arc<State>, at: Instant) {
+        let weak = Arc::downgrade(state);
+        ::timer::schedule(at, move || if let Some(state) = weak.upgrade() {
+            State::check(&state);
+        });
+    }
+
+    // fires if the deadline passed, otherwise waits for the current one.
+    fn check(state: &Arc<State>) {
+        let deadline = *state.deadline.lock();
+        match deadline {
+            Some(deadline) if deadline <= Instant::now() => {
+                let _ = state.signal.fire();
+            }
+            Some(deadline) => State::arm(state, deadline),
+            None => {}
+        }
+    }
+}
+
+/// Signal that fires unless it's petted at least once every interval.
+///
+/// Clones share the same deadline. The signal also fires once every clone
+/// has been dropped.
+#[derive(Clone)]
+pub struct Watchdog {
+    state: Arc<State>,
+}
+
+impl Watchdog {
+    /// Create a watchdog that fires if `interval` passes without a `pet`.
+    pub fn new(interval: Duration) -> Watchdog {
+        let deadline = Instant::now().checked_add(interval);
+        let state = Arc::new(State { signal: ::signal_only(), interval, deadline: Mutex::new(deadline) });
+
+        if let Some(deadline) = deadline {
+            State::arm(&state, deadline);
+        }
+
+        Watchdog { state }
+    }
+
+    /// Push the deadline back to a full interval from now. This does
+    /// nothing once the watchdog has fired.
+    pub fn pet(&self) {
+        let deadline = Instant::now().checked_add(self.state.interval);
+        let mut current = self.state.deadline.lock();
+
+        // the timer only ever waits for a deadline that was set, so re-arm
+        // it if it had stopped.
+        if current.is_none() {
+            if let Some(deadline) = deadline {
+                State::arm(&self.state, deadline);
+            }
+        }
+        *current = deadline;
+    }
+
+    /// Get an exit which fires when the watchdog does.
+    pub fn exit(&self) -> Exit {
+        self.state.signal.make_exit()
+    }
+
+    /// Whether the watchdog has fired.
+    pub fn is_fired(&self) -> bool {
+        self.state.signal.is_fired()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn fires_unless_petted() {
+        let watchdog = Watchdog::new(Duration::from_millis(100));
+        let exit = watchdog.exit();
+
+        for _ in 0..5 {
+            thread::sleep(Duration::from_millis(20));
+            watchdog.pet();
+        }
+        assert!(exit.is_live());
+
+        assert_eq!(Future::wait(exit), Ok(()));
+        assert!(watchdog.is_fired());
+    }
+
+    #[test]
+    fn fires_on_drop() {
+        let watchdog = Watchdog::new(Duration::from_secs(3600));
+        let exit = watchdog.exit();
+
+        drop(watchdog.clone());
+        assert!(exit.is_live());
+        drop(watchdog);
+        assert!(!exit.is_live());
+    }
+}
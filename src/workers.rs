@@ -0,0 +1,220 @@
+use parking_lot::{Condvar, Mutex};
+
+use std::io;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use {Exit, Signal};
+
+// ids of the workers which stopped, notified as they do.
+#[derive(Default)]
+struct Stopped {
+    ids: Mutex<Vec<usize>>,
+    changed: Condvar,
+}
+
+// marks a worker stopped once dropped, including while it unwinds.
+struct StopGuard {
+    stopped: Arc<Stopped>,
+    id: usize,
+}
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        self.stopped.ids.lock().push(self.id);
+        self.stopped.changed.notify_all();
+    }
+}
+
+struct Worker {
+    name: String,
+    // `None` for registered threads, which are joined by a watcher thread.
+    handle: Option<JoinHandle<()>>,
+    // overrides the one given to `shutdown`.
+    timeout: Option<Duration>,
+}
+
+/// Registry of worker threads sharing one exit, which fires it on shutdown
+/// and joins them.
+pub struct Workers {
+    signal: Signal,
+    workers: Vec<Worker>,
+    stopped: Arc<Stopped>,
+}
+
+impl Workers {
+    /// Create a registry with no threads.
+    pub fn new() -> Workers {
+        Workers { signal: ::signal_only(), workers: Vec::new(), stopped: Arc::default() }
+    }
+
+    /// Get an exit which fires when shutdown starts.
+    pub fn exit(&self) -> Exit {
+        self.signal.make_exit()
+    }
+
+    /// Spawn a named worker thread, passing it the exit.
+    pub fn spawn<F>(&mut self, name: &str, f: F) -> io::Result<()>
+        where F: FnOnce(Exit) + Send + 'static
+    {
+        self.spawn_inner(name, None, f)
+    }
+
+    /// Spawn a named worker thread like `spawn`, which is given `timeout`
+    /// to stop on shutdown instead of the timeout passed to `shutdown`.
+    pub fn spawn_with_timeout<F>(&mut self, name: &str, timeout: Duration, f: F) -> io::Result<()>
+        where F: FnOnce(Exit) + Send + 'static
+    {
+        self.spawn_inner(name, Some(timeout), f)
+    }
+
+    fn spawn_inner<F>(&mut self, name: &str, timeout: Option<Duration>, f: F) -> io::Result<()>
+        where F: FnOnce(Exit) + Send + 'static
+    {
+        let exit = self.exit();
+        let guard = self.guard();
+        let handle = thread::Builder::new().name(name.to_owned()).spawn(move || {
+            let _guard = guard;
+            f(exit)
+        })?;
+
+        self.workers.push(Worker { name: name.to_owned(), handle: Some(handle), timeout });
+        Ok(())
+    }
+
+    /// Register a thread spawned elsewhere, which should stop once the
+    /// exit fires.
+    ///
+    /// The thread is joined by a watcher thread, which this spawns.
+    pub fn register(&mut self, name: &str, handle: JoinHandle<()>) -> io::Result<()> {
+        self.register_inner(name, None, handle)
+    }
+
+    /// Register a thread spawned elsewhere like `register`, which is given
+    /// `timeout` to stop on shutdown instead of the timeout passed to
+    /// `shutdown`.
+    pub fn register_with_timeout(&mut self, name: &str, timeout: Duration, handle: JoinHandle<()>) -> io::Result<()> {
+        self.register_inner(name, Some(timeout), handle)
+    }
+
+    fn register_inner(&mut self, name: &str, timeout: Option<Duration>, handle: JoinHandle<()>) -> io::Result<()> {
+        let guard = self.guard();
+        let _ = thread::Builder::new().name(format!("{}-join", name)).spawn(move || {
+            let _guard = guard;
+            let _ = handle.join();
+        })?;
+
+        self.workers.push(Worker { name: name.to_owned(), handle: None, timeout });
+        Ok(())
+    }
+
+    fn guard(&self) -> StopGuard {
+        StopGuard { stopped: self.stopped.clone(), id: self.workers.len() }
+    }
+
+    /// Fire the exit and join every thread, giving each up to its timeout,
+    /// or `timeout` if it has none, from the fire to stop. Returns the names
+    /// of threads which didn't, in registration order; they are left
+    /// running, detached.
+    ///
+    /// Threads that panicked count as stopped.
+    pub fn shutdown(self, timeout: Duration) -> Vec<String> {
+        let _ = self.signal.fire();
+        let start = Instant::now();
+
+        // a timeout too large to represent is the same as none at all.
+        let mut deadlines: Vec<_> = self.workers.iter()
+            .map(|worker| Some(start.checked_add(worker.timeout.unwrap_or(timeout))))
+            .collect();
+        let mut timed_out = vec![false; self.workers.len()];
+
+        let mut ids = self.stopped.ids.lock();
+        loop {
+            for id in ids.drain(..) {
+                deadlines[id] = None;
+            }
+
+            let now = Instant::now();
+            for (id, deadline) in deadlines.iter_mut().enumerate() {
+                if let Some(Some(at)) = *deadline {
+                    if at <= now {
+                        timed_out[id] = true;
+                        *deadline = None;
+                    }
+                }
+            }
+
+            if deadlines.iter().all(Option::is_none) {
+                break
+            }
+
+            // woken as workers stop, or at the earliest deadline.
+            match deadlines.iter().flatten().flatten().min() {
+                Some(&at) => { let _ = self.stopped.changed.wait_until(&mut ids, at); }
+                None => self.stopped.changed.wait(&mut ids),
+            }
+        }
+        drop(ids);
+
+        let mut running = Vec::new();
+        for (worker, timed_out) in self.workers.into_iter().zip(timed_out) {
+            if timed_out {
+                running.push(worker.name);
+            } else if let Some(handle) = worker.handle {
+                // already on its way out.
+                let _ = handle.join();
+            }
+        }
+
+        running
+    }
+}
+
+impl Default for Workers {
+    fn default() -> Workers {
+        Workers::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_stopped_threads() {
+        let mut workers = Workers::new();
+        for i in 0..3 {
+            workers.spawn(&format!("worker-{}", i), |exit| { let _ = exit.wait(); }).unwrap();
+        }
+
+        let exit = workers.exit();
+        workers.register("registered", thread::spawn(move || { let _ = exit.wait(); })).unwrap();
+
+        assert!(workers.shutdown(Duration::from_millis(5000)).is_empty());
+    }
+
+    #[test]
+    fn reports_stuck_threads() {
+        let mut workers = Workers::new();
+        workers.spawn("stopping", |exit| { let _ = exit.wait(); }).unwrap();
+        workers.spawn("stuck", |_| thread::sleep(Duration::from_millis(200))).unwrap();
+        workers.spawn("panicking", |_| panic!("worker failed")).unwrap();
+
+        assert_eq!(workers.shutdown(Duration::from_millis(20)), vec!["stuck".to_owned()]);
+    }
+
+    #[test]
+    fn per_worker_timeouts() {
+        let mut workers = Workers::new();
+        let slow = || thread::sleep(Duration::from_millis(100));
+        workers.spawn_with_timeout("patient", Duration::from_millis(5000), move |_| slow()).unwrap();
+        workers.spawn("impatient", move |_| slow()).unwrap();
+        workers.register_with_timeout("registered", Duration::from_millis(5000), thread::spawn(slow)).unwrap();
+
+        let start = Instant::now();
+        assert_eq!(workers.shutdown(Duration::from_millis(20)), vec!["impatient".to_owned()]);
+        // returns as soon as the patient workers stop.
+        assert!(start.elapsed() < Duration::from_millis(5000));
+    }
+}
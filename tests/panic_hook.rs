@@ -0,0 +1,19 @@
+// a binary of its own: the hook is process-wide, so panics from other tests
+// running alongside would fire it too.
+#![cfg(feature = "std")]
+
+extern crate exit_future;
+
+use std::thread;
+
+use exit_future::install_panic_hook;
+
+#[test]
+fn fires_on_panic() {
+    let (signal, exit) = exit_future::signal();
+    install_panic_hook(&signal);
+    assert!(exit.is_live());
+
+    assert!(thread::spawn(|| panic!("background thread failed")).join().is_err());
+    assert!(!exit.is_live());
+}